@@ -12,10 +12,10 @@ use sn_dbc::{
     mock,
     rand::{CryptoRng, RngCore},
     random_derivation_index, rng, Dbc, DbcIdSource, Hash, MainKey, Result, Token,
-    TransactionVerifier,
+    TransactionVerifier, VerifiedTxCache, DEFAULT_VERIFIED_TX_CACHE_CAPACITY,
 };
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
 use std::collections::{BTreeMap, BTreeSet};
 
 const N_OUTPUTS: u64 = 100;
@@ -107,26 +107,44 @@ fn bench_reissue_100_to_1(c: &mut Criterion) {
     let main_key = MainKey::random_from_rng(&mut rng);
     let derivation_index = random_derivation_index(&mut rng);
 
-    let mut tx_builder = sn_dbc::TransactionBuilder::default();
-
-    for (dbc, _) in dbcs.into_iter() {
-        let (main_key, _, _) = outputs.get(&dbc.id()).unwrap();
-        let derived_key = dbc.derived_key(main_key).unwrap();
-        tx_builder = tx_builder.add_input_dbc(&dbc, &derived_key).unwrap();
-    }
-
-    let merge_dbc_builder = tx_builder
-        .add_output(
-            Token::from_nano(N_OUTPUTS),
-            DbcIdSource {
-                public_address: main_key.public_address(),
-                derivation_index,
+    let inputs: Vec<_> = dbcs
+        .into_iter()
+        .map(|(dbc, _)| {
+            let (main_key, _, _) = outputs.get(&dbc.id()).unwrap();
+            let derived_key = dbc.derived_key(main_key).unwrap();
+            (dbc, derived_key)
+        })
+        .collect();
+    let merge_tx_builder = || {
+        sn_dbc::TransactionBuilder::default()
+            .add_input_dbcs(&inputs)
+            .unwrap()
+            .add_output(
+                Token::from_nano(N_OUTPUTS),
+                DbcIdSource {
+                    public_address: main_key.public_address(),
+                    derivation_index,
+                },
+            )
+    };
+
+    // Signs each of the inputs.
+    c.bench_function(&format!("build merge {N_OUTPUTS} to 1"), |b| {
+        b.iter_batched(
+            merge_tx_builder,
+            |tx_builder| {
+                tx_builder
+                    .build(Hash::default(), rng::from_seed([0u8; 32]))
+                    .unwrap()
             },
-        )
-        .build(Hash::default(), &mut rng)
-        .unwrap();
+            BatchSize::LargeInput,
+        );
+    });
+
+    let merge_dbc_builder = merge_tx_builder().build(Hash::default(), &mut rng).unwrap();
 
     let merge_spent_tx = merge_dbc_builder.spent_tx.clone();
+    let unlogged_spentbook_node = spentbook_node.clone();
     for signed_spend in merge_dbc_builder.signed_spends() {
         spentbook_node
             .log_spent(&merge_spent_tx, signed_spend)
@@ -154,6 +172,53 @@ fn bench_reissue_100_to_1(c: &mut Criterion) {
             report.flamegraph(file).unwrap();
         };
     });
+
+    // The input signatures are verified with a single multi-pairing.
+    let aggregated_dbc_builder = merge_tx_builder()
+        .aggregate_signatures()
+        .build(Hash::default(), &mut rng)
+        .unwrap();
+    let aggregated_signed_spends: BTreeSet<_> = aggregated_dbc_builder
+        .signed_spends()
+        .into_iter()
+        .cloned()
+        .collect();
+    c.bench_function(
+        &format!("reissue merge {N_OUTPUTS} to 1 with aggregated signature"),
+        |b| {
+            b.iter(|| {
+                TransactionVerifier::verify(
+                    black_box(&aggregated_dbc_builder.spent_tx),
+                    &aggregated_signed_spends,
+                )
+                .unwrap();
+            });
+        },
+    );
+
+    // Logging each of the spends verifies the tx, unless it is found in the cache.
+    for (name, capacity) in [("without", 0), ("with", DEFAULT_VERIFIED_TX_CACHE_CAPACITY)] {
+        c.bench_function(
+            &format!("log spent merge {N_OUTPUTS} to 1 {name} verified tx cache"),
+            |b| {
+                b.iter_batched(
+                    || {
+                        let mut spentbook_node = unlogged_spentbook_node.clone();
+                        spentbook_node.verified_txs = VerifiedTxCache::new(capacity);
+                        spentbook_node
+                    },
+                    |mut spentbook_node| {
+                        for signed_spend in merge_dbc_builder.signed_spends() {
+                            spentbook_node
+                                .log_spent(black_box(&merge_spent_tx), signed_spend)
+                                .unwrap();
+                        }
+                    },
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
 }
 
 #[allow(clippy::result_large_err)]
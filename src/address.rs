@@ -0,0 +1,407 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Checksummed string encoding of `PublicAddress` and `DbcId`.
+//!
+//! The key is encoded with bech32m (BIP-350), behind a prefix naming the network and
+//! what the key is, eg. `safe1...` for a mainnet `PublicAddress`, or `tsafeid1...` for
+//! a testnet `DbcId`. A mistyped character is caught by the checksum, and usually
+//! located, and an address of another network, or a `DbcId` given as an address, is
+//! caught by its prefix.
+//!
+//! `Display` and `FromStr` use the mainnet form. For another network, see
+//! `to_bech32()` and `from_bech32()`.
+
+use crate::{DbcId, NetworkId, PublicAddress, PublicKey};
+
+use blsttc::PK_SIZE;
+use std::{convert::TryInto, fmt, str::FromStr};
+use thiserror::Error;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const SEPARATOR: char = '1';
+const CHECKSUM_LEN: usize = 6;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+/// An error of parsing a `PublicAddress` or `DbcId` string.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AddressParseError {
+    #[error("The string mixes upper and lower case.")]
+    MixedCase,
+    #[error("The string has no '1' separator after its prefix.")]
+    MissingSeparator,
+    #[error("Unexpected character {character:?} at index {index}.")]
+    InvalidCharacter { character: char, index: usize },
+    #[error("The prefix is {found:?}, where {expected:?} was expected.")]
+    WrongPrefix { expected: String, found: String },
+    #[error("Invalid checksum{}.", match .index { Some(i) => format!(", likely a typo at index {i}"), None => String::new() })]
+    InvalidChecksum { index: Option<usize> },
+    #[error("The string holds {0} bytes, where a key has 48.")]
+    InvalidLength(usize),
+    #[error("The string does not hold a valid key.")]
+    InvalidKey,
+}
+
+impl PublicAddress {
+    /// Encode this address for the network, eg. `safe1...` on mainnet.
+    pub fn to_bech32(&self, network: NetworkId) -> String {
+        encode(network.address_prefix(), &self.to_bytes())
+    }
+
+    /// Parse an address of the network, see `to_bech32()`.
+    pub fn from_bech32(s: &str, network: NetworkId) -> Result<Self, AddressParseError> {
+        decode_key(s, network.address_prefix()).map(Self::new)
+    }
+}
+
+impl DbcId {
+    /// Encode this id for the network, eg. `safeid1...` on mainnet.
+    pub fn to_bech32(&self, network: NetworkId) -> String {
+        encode(network.dbc_id_prefix(), &self.to_bytes())
+    }
+
+    /// Parse an id of the network, see `to_bech32()`.
+    pub fn from_bech32(s: &str, network: NetworkId) -> Result<Self, AddressParseError> {
+        decode_key(s, network.dbc_id_prefix()).map(Self::new)
+    }
+}
+
+impl fmt::Display for PublicAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_bech32(NetworkId::Mainnet))
+    }
+}
+
+impl fmt::Debug for PublicAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PublicAddress")
+            .field(&self.to_string())
+            .finish()
+    }
+}
+
+impl FromStr for PublicAddress {
+    type Err = AddressParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bech32(s, NetworkId::Mainnet)
+    }
+}
+
+impl fmt::Display for DbcId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_bech32(NetworkId::Mainnet))
+    }
+}
+
+impl fmt::Debug for DbcId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DbcId").field(&self.to_string()).finish()
+    }
+}
+
+impl FromStr for DbcId {
+    type Err = AddressParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bech32(s, NetworkId::Mainnet)
+    }
+}
+
+fn decode_key(s: &str, expected_prefix: &str) -> Result<PublicKey, AddressParseError> {
+    let bytes: [u8; PK_SIZE] = decode(s, expected_prefix)?
+        .try_into()
+        .map_err(|b: Vec<u8>| AddressParseError::InvalidLength(b.len()))?;
+    PublicKey::from_bytes(bytes).map_err(|_| AddressParseError::InvalidKey)
+}
+
+/// Encode the bytes as bech32m, behind the prefix.
+fn encode(prefix: &str, bytes: &[u8]) -> String {
+    let data = to_base32(bytes);
+    let checksum = checksum(prefix, &data);
+    let mut s = String::with_capacity(prefix.len() + 1 + data.len() + CHECKSUM_LEN);
+    s.push_str(prefix);
+    s.push(SEPARATOR);
+    s.extend(
+        data.iter()
+            .chain(checksum.iter())
+            .map(|v| CHARSET[*v as usize] as char),
+    );
+    s
+}
+
+/// Decode the bytes of a bech32m string with the expected prefix.
+fn decode(s: &str, expected_prefix: &str) -> Result<Vec<u8>, AddressParseError> {
+    let has_lower = s.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = s.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return Err(AddressParseError::MixedCase);
+    }
+    let s = s.to_ascii_lowercase();
+
+    let separator = s
+        .rfind(SEPARATOR)
+        .filter(|i| *i > 0)
+        .ok_or(AddressParseError::MissingSeparator)?;
+    for (index, character) in s.chars().enumerate() {
+        if !(33..=126).contains(&(character as u32)) {
+            return Err(AddressParseError::InvalidCharacter { character, index });
+        }
+    }
+    let (prefix, rest) = s.split_at(separator);
+    if prefix != expected_prefix {
+        return Err(AddressParseError::WrongPrefix {
+            expected: expected_prefix.to_string(),
+            found: prefix.to_string(),
+        });
+    }
+
+    let data_start = separator + 1;
+    let data = rest[1..]
+        .chars()
+        .enumerate()
+        .map(|(i, character)| {
+            CHARSET
+                .iter()
+                .position(|c| *c as char == character)
+                .map(|v| v as u8)
+                .ok_or(AddressParseError::InvalidCharacter {
+                    character,
+                    index: data_start + i,
+                })
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+    if data.len() < CHECKSUM_LEN {
+        return Err(AddressParseError::InvalidChecksum { index: None });
+    }
+    if !verify_checksum(prefix, &data) {
+        return Err(AddressParseError::InvalidChecksum {
+            index: locate_typo(prefix, &data).map(|i| data_start + i),
+        });
+    }
+
+    let payload = &data[..data.len() - CHECKSUM_LEN];
+    from_base32(payload).ok_or(AddressParseError::InvalidLength(payload.len() * 5 / 8))
+}
+
+/// The index of the one character which, when changed, makes the checksum valid.
+/// None if there is no such character, or more than one.
+fn locate_typo(prefix: &str, data: &[u8]) -> Option<usize> {
+    let mut fixable = (0..data.len()).filter(|i| {
+        (0..32u8).filter(|v| *v != data[*i]).any(|v| {
+            let mut fixed = data.to_vec();
+            fixed[*i] = v;
+            verify_checksum(prefix, &fixed)
+        })
+    });
+    match (fixable.next(), fixable.next()) {
+        (Some(i), None) => Some(i),
+        _ => None,
+    }
+}
+
+fn polymod(values: impl IntoIterator<Item = u8>) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk: u32 = 1;
+    for value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ value as u32;
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn expand_prefix(prefix: &str) -> impl Iterator<Item = u8> + '_ {
+    prefix
+        .bytes()
+        .map(|b| b >> 5)
+        .chain([0])
+        .chain(prefix.bytes().map(|b| b & 0x1f))
+}
+
+fn checksum(prefix: &str, data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let values = expand_prefix(prefix)
+        .chain(data.iter().copied())
+        .chain([0; CHECKSUM_LEN]);
+    let pm = polymod(values) ^ BECH32M_CONST;
+    let mut checksum = [0u8; CHECKSUM_LEN];
+    for (i, c) in checksum.iter_mut().enumerate() {
+        *c = ((pm >> (5 * (CHECKSUM_LEN - 1 - i))) & 0x1f) as u8;
+    }
+    checksum
+}
+
+fn verify_checksum(prefix: &str, data: &[u8]) -> bool {
+    polymod(expand_prefix(prefix).chain(data.iter().copied())) == BECH32M_CONST
+}
+
+/// Regroup 8 bit bytes into 5 bit values, padding the last with zeros.
+fn to_base32(bytes: &[u8]) -> Vec<u8> {
+    let mut values = Vec::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut acc, mut bits) = (0u32, 0);
+    for byte in bytes {
+        acc = (acc << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            values.push(((acc >> bits) & 0x1f) as u8);
+        }
+    }
+    if bits > 0 {
+        values.push(((acc << (5 - bits)) & 0x1f) as u8);
+    }
+    values
+}
+
+/// Regroup 5 bit values into 8 bit bytes. None if the padding is not zeros.
+fn from_base32(values: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(values.len() * 5 / 8);
+    let (mut acc, mut bits) = (0u32, 0);
+    for value in values {
+        acc = ((acc << 5) | *value as u32) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || (acc << (8 - bits)) & 0xff != 0 {
+        return None;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MainKey;
+
+    #[test]
+    fn bech32m_test_vectors() {
+        // Valid bech32m strings from BIP-350.
+        for s in [
+            "a1lqfn3a",
+            "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+            "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+            "?1v759aa",
+        ] {
+            let (prefix, data) = s.split_at(s.rfind(SEPARATOR).unwrap_or_default());
+            let data: Vec<u8> = data[1..]
+                .bytes()
+                .filter_map(|c| CHARSET.iter().position(|v| *v == c).map(|v| v as u8))
+                .collect();
+            assert!(verify_checksum(prefix, &data), "{:?}", s);
+        }
+        let bytes = decode("abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx", "abcdef")
+            .expect("valid bech32m");
+        assert_eq!(
+            encode("abcdef", &bytes),
+            "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx"
+        );
+    }
+
+    #[test]
+    fn addresses_and_ids_round_trip() -> Result<(), AddressParseError> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let main_key = MainKey::random_from_rng(&mut rng);
+        let address = main_key.public_address();
+        let dbc_id = main_key.random_dbc_id_src(&mut rng).dbc_id();
+
+        let s = address.to_string();
+        assert!(s.starts_with("safe1"));
+        assert_eq!(s.parse::<PublicAddress>()?, address);
+        assert_eq!(s.to_uppercase().parse::<PublicAddress>()?, address);
+        assert_eq!(format!("{:?}", address), format!("PublicAddress({:?})", s));
+
+        let s = dbc_id.to_string();
+        assert!(s.starts_with("safeid1"));
+        assert_eq!(s.parse::<DbcId>()?, dbc_id);
+        assert_eq!(format!("{:?}", dbc_id), format!("DbcId({:?})", s));
+
+        let s = address.to_bech32(NetworkId::Testnet);
+        assert!(s.starts_with("tsafe1"));
+        assert_eq!(PublicAddress::from_bech32(&s, NetworkId::Testnet)?, address);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors_are_located() {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let address = MainKey::random_from_rng(&mut rng).public_address();
+        let s = address.to_string();
+
+        // A testnet address on mainnet, or an id given as an address.
+        let testnet = address.to_bech32(NetworkId::Testnet);
+        assert_eq!(
+            testnet.parse::<PublicAddress>(),
+            Err(AddressParseError::WrongPrefix {
+                expected: "safe".to_string(),
+                found: "tsafe".to_string()
+            })
+        );
+        let dbc_id = MainKey::random_from_rng(&mut rng)
+            .random_dbc_id_src(&mut rng)
+            .dbc_id();
+        assert_eq!(
+            dbc_id.to_string().parse::<PublicAddress>(),
+            Err(AddressParseError::WrongPrefix {
+                expected: "safe".to_string(),
+                found: "safeid".to_string()
+            })
+        );
+        // Nor does changing the prefix get past the checksum.
+        assert!(matches!(
+            s.replacen("safe", "safeid", 1).parse::<DbcId>(),
+            Err(AddressParseError::InvalidChecksum { .. })
+        ));
+
+        // A mistyped character.
+        for index in [5, 20, s.len() - 1] {
+            let mut typo = s.clone().into_bytes();
+            typo[index] = if typo[index] == b'q' { b'p' } else { b'q' };
+            let typo = String::from_utf8(typo).expect("ascii");
+            assert_eq!(
+                typo.parse::<PublicAddress>(),
+                Err(AddressParseError::InvalidChecksum { index: Some(index) })
+            );
+        }
+        // A character not in the charset.
+        let mut invalid = s.clone();
+        invalid.replace_range(10..11, "b");
+        assert_eq!(
+            invalid.parse::<PublicAddress>(),
+            Err(AddressParseError::InvalidCharacter {
+                character: 'b',
+                index: 10
+            })
+        );
+        // Cut short.
+        assert!(matches!(
+            s[..s.len() - 2].parse::<PublicAddress>(),
+            Err(AddressParseError::InvalidChecksum { .. })
+        ));
+        assert_eq!(
+            format!("SAFE{}", &s[4..]).parse::<PublicAddress>(),
+            Err(AddressParseError::MixedCase)
+        );
+        assert_eq!(
+            "safe".parse::<PublicAddress>(),
+            Err(AddressParseError::MissingSeparator)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    transaction::{DbcTransaction, RevealedAmount},
+    Ciphertext, Dbc, DbcId, DerivedKey, Error, MainKey, PublicAddress, Result, Signature,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The amount of one Dbc, disclosed to an auditor.
+///
+/// The RevealedAmount of the Dbc is encrypted to the PublicAddress of the auditor, and
+/// signed by the DerivedKey of the Dbc, so that the auditor knows it comes from the
+/// holder of the Dbc. The auditor checks it against the blinded amount of the Dbc in
+/// its src_tx, and learns nothing about any other Dbc, nor the MainKey of the holder.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountDisclosure {
+    pub dbc_id: DbcId,
+    pub auditor: PublicAddress,
+    pub revealed_amount_cipher: Ciphertext,
+    pub derived_key_sig: Signature,
+}
+
+impl AmountDisclosure {
+    /// Disclose the amount of the Dbc to the auditor.
+    pub fn new(dbc: &Dbc, derived_key: &DerivedKey, auditor: &PublicAddress) -> Result<Self> {
+        if derived_key.dbc_id() != dbc.id() {
+            return Err(Error::DbcIdNotFound);
+        }
+        let revealed_amount = dbc.revealed_amount(derived_key)?;
+        let revealed_amount_cipher = auditor.encrypt_amount(&revealed_amount);
+        let msg = Self::msg(&dbc.id(), auditor, &revealed_amount_cipher);
+
+        Ok(Self {
+            dbc_id: dbc.id(),
+            auditor: *auditor,
+            revealed_amount_cipher,
+            derived_key_sig: derived_key.sign(&msg),
+        })
+    }
+
+    /// Decrypt the disclosed amount with the MainKey of the auditor, and check it is
+    /// the amount of the output of the Dbc in its src_tx.
+    pub fn reveal(&self, auditor_key: &MainKey, src_tx: &DbcTransaction) -> Result<RevealedAmount> {
+        if auditor_key.public_address() != self.auditor {
+            return Err(Error::MainKeyDoesNotMatchPublicAddress);
+        }
+        let msg = Self::msg(&self.dbc_id, &self.auditor, &self.revealed_amount_cipher);
+        if !self.dbc_id.verify(&self.derived_key_sig, msg) {
+            return Err(Error::InvalidAmountDisclosureSignature(self.dbc_id));
+        }
+
+        let revealed_amount = auditor_key.decrypt_amount(&self.revealed_amount_cipher)?;
+        let blinded_amount_in_tx = src_tx
+            .outputs
+            .iter()
+            .find(|o| o.dbc_id() == &self.dbc_id)
+            .ok_or(Error::BlindedOutputNotFound)?
+            .blinded_amount();
+        if revealed_amount.blinded_amount(&Default::default()) != blinded_amount_in_tx {
+            return Err(Error::BlindedAmountsDoNotMatch);
+        }
+        Ok(revealed_amount)
+    }
+
+    /// The message signed by the DerivedKey of the Dbc.
+    fn msg(dbc_id: &DbcId, auditor: &PublicAddress, cipher: &Ciphertext) -> Vec<u8> {
+        let mut v: Vec<u8> = Default::default();
+        v.extend("amount_disclosure".as_bytes());
+        v.extend(dbc_id.to_bytes().as_ref());
+        v.extend(auditor.to_bytes().as_ref());
+        v.extend(cipher.to_bytes());
+        v
+    }
+}
+
+impl Dbc {
+    /// Disclose the amount of this Dbc to an auditor, see `AmountDisclosure`.
+    pub fn disclose_amount(
+        &self,
+        derived_key: &DerivedKey,
+        auditor: &PublicAddress,
+    ) -> Result<AmountDisclosure> {
+        AmountDisclosure::new(self, derived_key, auditor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auditor_sees_the_amount_of_one_dbc() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, main_key) =
+            crate::dbc::tests::generate_dbc_and_its_main_key(250, &mut rng)?;
+        let derived_key = dbc.derived_key(&main_key)?;
+        let auditor_key = MainKey::random_from_rng(&mut rng);
+        let auditor = auditor_key.public_address();
+
+        let disclosure = dbc.disclose_amount(&derived_key, &auditor)?;
+        let revealed_amount = disclosure.reveal(&auditor_key, &dbc.src_tx)?;
+        assert_eq!(revealed_amount.value(), 250);
+
+        // Only the auditor can see it.
+        let other_key = MainKey::random_from_rng(&mut rng);
+        assert_eq!(
+            disclosure.reveal(&other_key, &dbc.src_tx).map(|_| ()),
+            Err(Error::MainKeyDoesNotMatchPublicAddress)
+        );
+
+        // An amount other than the one in the tx is caught.
+        let mut forged = disclosure.clone();
+        let mut forged_amount = revealed_amount;
+        forged_amount.value = 1_000;
+        forged.revealed_amount_cipher = auditor.encrypt_amount(&forged_amount);
+        assert_eq!(
+            forged.reveal(&auditor_key, &dbc.src_tx).map(|_| ()),
+            Err(Error::InvalidAmountDisclosureSignature(dbc.id()))
+        );
+        let msg = AmountDisclosure::msg(&dbc.id(), &auditor, &forged.revealed_amount_cipher);
+        forged.derived_key_sig = derived_key.sign(&msg);
+        assert_eq!(
+            forged.reveal(&auditor_key, &dbc.src_tx).map(|_| ()),
+            Err(Error::BlindedAmountsDoNotMatch)
+        );
+
+        // The disclosure is of this Dbc only.
+        let (_, _, other_dbc, _) = crate::dbc::tests::generate_dbc_and_its_main_key(250, &mut rng)?;
+        assert_eq!(
+            disclosure
+                .reveal(&auditor_key, &other_dbc.src_tx)
+                .map(|_| ()),
+            Err(Error::BlindedOutputNotFound)
+        );
+        Ok(())
+    }
+}
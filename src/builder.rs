@@ -14,7 +14,7 @@ use crate::{
     transaction::{
         DbcTransaction, InputHistory, Output, RevealedAmount, RevealedOutput, RevealedTx,
     },
-    DbcId, DerivedKey,
+    DbcId, DerivedKey, NetworkId,
 };
 use crate::{
     rand::{CryptoRng, RngCore},
@@ -33,6 +33,7 @@ pub type OutputIdSources = BTreeMap<DbcId, DbcIdSource>;
 pub struct TransactionBuilder {
     revealed_tx: RevealedTx,
     output_id_sources: OutputIdSources,
+    aggregate_signatures: bool,
 }
 
 impl TransactionBuilder {
@@ -109,6 +110,22 @@ impl TransactionBuilder {
         Token::from_nano(amount)
     }
 
+    /// Aggregate the signatures of the inputs into a single signature of the tx.
+    ///
+    /// Only the input signatures of the tx are aggregated. Each SignedSpend is still
+    /// signed on its own, as it is logged and verified separately from the others.
+    pub fn aggregate_signatures(mut self) -> Self {
+        self.aggregate_signatures = true;
+        self
+    }
+
+    /// Sign the tx for the network, mainnet by default.
+    /// The inputs must all have been created on that network.
+    pub fn network(mut self, network: NetworkId) -> Self {
+        self.revealed_tx.network = network;
+        self
+    }
+
     /// Get inputs.
     pub fn inputs(&self) -> &Vec<InputHistory> {
         &self.revealed_tx.inputs
@@ -122,32 +139,49 @@ impl TransactionBuilder {
     /// Build the DbcTransaction by signing the inputs,
     /// and generating the blinded outputs. Return a DbcBuilder.
     pub fn build(self, reason: Hash, rng: impl RngCore + CryptoRng) -> Result<DbcBuilder> {
-        let (spent_tx, revealed_outputs) = self.revealed_tx.sign(rng)?;
-
-        let signed_spends: BTreeSet<_> = spent_tx
+        let network = self.revealed_tx.network;
+        if let Some(input) = self
+            .revealed_tx
             .inputs
             .iter()
-            .flat_map(|input| {
-                self.revealed_tx
-                    .inputs
-                    .iter()
-                    .find(|i| i.input.dbc_id() == input.dbc_id())
-                    .map(|i| {
-                        let spend = crate::Spend {
-                            dbc_id: input.dbc_id(),
-                            spent_tx: spent_tx.clone(),
-                            reason,
-                            blinded_amount: input.blinded_amount,
-                            dbc_creation_tx: i.input_src_tx.clone(),
-                        };
-                        let derived_key_sig = i.input.derived_key.sign(&spend.to_bytes());
-                        SignedSpend {
-                            spend,
-                            derived_key_sig,
-                        }
-                    })
-            })
-            .collect();
+            .find(|i| i.input_src_tx.network != network)
+        {
+            return Err(Error::NetworkMismatch {
+                expected: network,
+                found: input.input_src_tx.network,
+            });
+        }
+
+        let (spent_tx, revealed_outputs) = if self.aggregate_signatures {
+            self.revealed_tx.sign_aggregated(rng)?
+        } else {
+            self.revealed_tx.sign(rng)?
+        };
+
+        let signed_spends: BTreeSet<_> = crate::parallel::map(&spent_tx.inputs, |input| {
+            self.revealed_tx
+                .inputs
+                .iter()
+                .find(|i| i.input.dbc_id() == input.dbc_id())
+                .map(|i| {
+                    let spend = crate::Spend {
+                        version: crate::Spend::VERSION,
+                        dbc_id: input.dbc_id(),
+                        spent_tx: spent_tx.clone(),
+                        reason,
+                        blinded_amount: input.blinded_amount,
+                        dbc_creation_tx: i.input_src_tx.clone(),
+                    };
+                    let derived_key_sig = i.input.derived_key.sign(&spend.to_bytes());
+                    SignedSpend {
+                        spend,
+                        derived_key_sig,
+                    }
+                })
+        })
+        .into_iter()
+        .flatten()
+        .collect();
 
         Ok(DbcBuilder::new(
             spent_tx,
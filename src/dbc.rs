@@ -6,17 +6,20 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use std::{collections::BTreeSet, convert::TryFrom};
+use std::{collections::BTreeSet, convert::TryFrom, ops::RangeBounds};
 use tiny_keccak::{Hasher, Sha3};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::dbc_id::PublicAddress;
-use crate::transaction::{BlindedOutput, DbcTransaction, RevealedAmount, RevealedInput};
+use crate::transaction::{
+    Amount, AmountRangeProof, BlindedOutput, DbcTransaction, RevealedAmount, RevealedInput,
+};
 use crate::{
+    rand::{CryptoRng, RngCore},
     BlindedAmount, DbcCiphers, DbcId, DerivationIndex, DerivedKey, Error, Hash, MainKey, Result,
-    SignedSpend, TransactionVerifier,
+    SignedSpend, TransactionVerifier, VerificationReport,
 };
 
 /// Represents a Digital Bearer Certificate (Dbc).
@@ -135,6 +138,23 @@ impl Dbc {
             .blinded_amount())
     }
 
+    /// Prove that the amount of this Dbc is within the range, e.g. at least 100 with
+    /// `100..`, without revealing the amount. See `AmountRangeProof`.
+    pub fn prove_amount_range(
+        &self,
+        derived_key: &DerivedKey,
+        range: impl RangeBounds<Amount>,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<AmountRangeProof> {
+        AmountRangeProof::prove(&self.revealed_amount(derived_key)?, range, rng)
+    }
+
+    /// Verify a proof that the amount of this Dbc is within the bounds of the proof.
+    /// Only the blinded amount of the Dbc is used, so anyone holding the Dbc can verify.
+    pub fn verify_amount_range(&self, proof: &AmountRangeProof) -> Result<()> {
+        proof.verify(self.blinded_amount()?)
+    }
+
     /// Generate the hash of this Dbc
     pub fn hash(&self) -> Hash {
         let mut sha3 = Sha3::v256();
@@ -185,7 +205,17 @@ impl Dbc {
         self.verify_amounts(main_key)
     }
 
+    /// Runs every check of `verify()`, instead of returning on the first failure,
+    /// and reports the outcome of each.
+    pub fn verify_report(&self, main_key: &MainKey) -> VerificationReport {
+        VerificationReport::of_dbc(self, main_key)
+    }
+
     /// Deserializes a `Dbc` represented as a hex string to a `Dbc`.
+    ///
+    /// Only the hex of this release is decoded, as the layout changes with the fields of
+    /// the Dbc, e.g. the input signatures that can be aggregated into one, since 19.1.0.
+    /// The hex of a Dbc of 19.1.0 is decoded and migrated by `Dbc::from_legacy_hex()`.
     #[cfg(feature = "serde")]
     pub fn from_hex(hex: &str) -> Result<Self, Error> {
         let mut bytes =
@@ -197,6 +227,7 @@ impl Dbc {
     }
 
     /// Serialize this `Dbc` instance to a hex string.
+    /// It can not be decoded by previous releases, see `Dbc::from_hex()`.
     #[cfg(feature = "serde")]
     pub fn to_hex(&self) -> Result<String, Error> {
         let mut serialized =
@@ -257,7 +288,6 @@ pub(crate) mod tests {
     use crate::{
         dbc_id::{random_derivation_index, DbcIdSource},
         mock,
-        rand::{CryptoRng, RngCore},
         transaction::{Output, RevealedTx},
         Hash, Token,
     };
@@ -275,6 +305,7 @@ pub(crate) mod tests {
         let tx_material = RevealedTx {
             inputs: vec![],
             outputs: vec![Output::new(derived_key.dbc_id(), amount)],
+            ..Default::default()
         };
         let (tx, revealed_amounts) = tx_material.sign(&mut rng).expect("Failed to sign tx");
         let ciphers = DbcCiphers::from((
@@ -308,6 +339,7 @@ pub(crate) mod tests {
         let tx_material = RevealedTx {
             inputs: vec![],
             outputs: vec![Output::new(derived_key.dbc_id(), amount)],
+            ..Default::default()
         };
         let (tx, revealed_amounts) = tx_material.sign(&mut rng).expect("Failed to sign tx");
         let ciphers = DbcCiphers::from((
@@ -368,6 +400,7 @@ pub(crate) mod tests {
         let tx_material = RevealedTx {
             inputs: vec![],
             outputs: vec![Output::new(derived_key.dbc_id(), amount)],
+            ..Default::default()
         };
 
         let (tx, revealed_amounts) = tx_material.sign(&mut rng).expect("Failed to sign tx");
@@ -397,6 +430,30 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    #[test]
+    fn amount_range_proof_of_a_dbc() -> Result<(), Error> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, main_key) = generate_dbc_and_its_main_key(250, &mut rng)?;
+        let derived_key = dbc.derived_key(&main_key)?;
+
+        let proof = dbc.prove_amount_range(&derived_key, 100.., &mut rng)?;
+        dbc.verify_amount_range(&proof)?;
+        assert_eq!(proof.min(), Some(100));
+        assert_eq!(
+            dbc.prove_amount_range(&derived_key, 251.., &mut rng)
+                .map(|_| ()),
+            Err(Error::AmountNotInRange)
+        );
+
+        // The proof is of the amount of this Dbc only.
+        let (_, _, other_dbc, _) = generate_dbc_and_its_main_key(250, &mut rng)?;
+        assert_eq!(
+            other_dbc.verify_amount_range(&proof),
+            Err(Error::InvalidAmountRangeProof)
+        );
+        Ok(())
+    }
+
     pub(crate) fn generate_dbc_of_value_from_pk_hex(
         amount: u64,
         pk_hex: &str,
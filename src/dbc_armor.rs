@@ -0,0 +1,228 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! An armored text encoding of Dbcs, for copying them through chat and email.
+//!
+//! ```text
+//! -----BEGIN SAFE DBC-----
+//! Version: 1
+//! DbcId: a1b2...
+//! Checksum: c3d4...
+//!
+//! <the compact encoding of the Dbc, in base64, wrapped at 64 characters>
+//! -----END SAFE DBC-----
+//! ```
+//!
+//! The checksum is the sha3 hash of the compact encoding, so that a mangled copy is
+//! told apart from a valid Dbc before it is decoded. A text may hold any number of
+//! armored Dbcs, and any text around them, such as the rest of an email, is ignored.
+
+use crate::{Dbc, Error, Hash, Result};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+/// The version of the armored format written by this crate.
+pub const DBC_ARMOR_VERSION: u32 = 1;
+
+const BEGIN: &str = "-----BEGIN SAFE DBC-----";
+const END: &str = "-----END SAFE DBC-----";
+const LINE_WIDTH: usize = 64;
+
+impl Dbc {
+    /// Encode this Dbc as armored text, see `from_armored()`.
+    pub fn to_armored(&self) -> Result<String> {
+        let bytes = self.to_compact_bytes()?;
+        let mut text = format!(
+            "{BEGIN}\nVersion: {DBC_ARMOR_VERSION}\nDbcId: {}\nChecksum: {}\n\n",
+            hex::encode(self.id().to_bytes()),
+            Hash::hash(&bytes).to_hex(),
+        );
+        let body = BASE64.encode(bytes);
+        for line in body.as_bytes().chunks(LINE_WIDTH) {
+            // base64 is ascii, so each chunk is valid utf8.
+            text.push_str(&String::from_utf8_lossy(line));
+            text.push('\n');
+        }
+        text.push_str(END);
+        text.push('\n');
+        Ok(text)
+    }
+
+    /// Encode the Dbcs as armored text, one after the other.
+    pub fn to_armored_many(dbcs: &[Dbc]) -> Result<String> {
+        Ok(dbcs
+            .iter()
+            .map(Dbc::to_armored)
+            .collect::<Result<Vec<_>>>()?
+            .join("\n"))
+    }
+
+    /// Decode every armored Dbc in the text, in the order they appear.
+    ///
+    /// Fails with `Error::ArmorTruncated` if a Dbc is cut short, and with
+    /// `Error::ArmorCorrupted` if its text was altered.
+    pub fn from_armored(text: &str) -> Result<Vec<Dbc>> {
+        let mut dbcs = vec![];
+        let mut lines = text.lines().map(str::trim);
+        while lines.any(|line| line == BEGIN) {
+            let mut block = vec![];
+            let mut ended = false;
+            for line in lines.by_ref() {
+                if line == END {
+                    ended = true;
+                    break;
+                }
+                block.push(line);
+            }
+            if !ended || block.contains(&BEGIN) {
+                return Err(Error::ArmorTruncated(format!(
+                    "Dbc #{} has no `{END}` line",
+                    dbcs.len() + 1
+                )));
+            }
+            dbcs.push(dearmor(&block, dbcs.len())?);
+        }
+        if dbcs.is_empty() {
+            return Err(Error::ArmorCorrupted(format!("no `{BEGIN}` line found")));
+        }
+        Ok(dbcs)
+    }
+}
+
+/// Decode the lines between the BEGIN and END lines of the nth armored Dbc.
+fn dearmor(block: &[&str], n: usize) -> Result<Dbc> {
+    let truncated = |what: &str| Error::ArmorTruncated(format!("Dbc #{}: {what}", n + 1));
+    let corrupted = |what: String| Error::ArmorCorrupted(format!("Dbc #{}: {what}", n + 1));
+
+    let mut headers = vec![];
+    let mut body = String::new();
+    let mut in_body = false;
+    for line in block.iter() {
+        if in_body {
+            body.push_str(line);
+        } else if line.is_empty() {
+            in_body = true;
+        } else {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| corrupted(format!("invalid header line `{line}`")))?;
+            headers.push((name.trim(), value.trim()));
+        }
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| *value)
+            .ok_or_else(|| corrupted(format!("the {name} header is missing")))
+    };
+
+    let version = header("Version")?;
+    if version != DBC_ARMOR_VERSION.to_string() {
+        return Err(Error::UnsupportedArmorVersion(version.to_string()));
+    }
+    let dbc_id = header("DbcId")?;
+    let checksum = Hash::from_hex(header("Checksum")?)
+        .map_err(|_| corrupted("the Checksum header is not a hash".to_string()))?;
+    if body.is_empty() {
+        return Err(truncated("it has no body"));
+    }
+
+    let bytes = BASE64.decode(&body).map_err(|e| match e {
+        base64::DecodeError::InvalidLength | base64::DecodeError::InvalidPadding => {
+            truncated("its body is incomplete")
+        }
+        e => corrupted(format!("its body is not base64: {e}")),
+    })?;
+    if Hash::hash(&bytes) != checksum {
+        return Err(corrupted("its checksum does not match".to_string()));
+    }
+    let dbc = Dbc::from_compact_bytes(&bytes)?;
+    if hex::encode(dbc.id().to_bytes()) != dbc_id {
+        return Err(corrupted("its DbcId header does not match".to_string()));
+    }
+    Ok(dbc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbc::tests::generate_dbc_and_its_main_key;
+
+    #[test]
+    fn armored_dbcs_round_trip() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, _) = generate_dbc_and_its_main_key(100, &mut rng)?;
+        let (_, _, other_dbc, _) = generate_dbc_and_its_main_key(200, &mut rng)?;
+
+        let armored = dbc.to_armored()?;
+        assert!(armored.starts_with(BEGIN));
+        let body = armored.lines().skip_while(|line| !line.is_empty()).skip(1);
+        assert!(body
+            .take_while(|line| *line != END)
+            .all(|line| line.len() <= LINE_WIDTH));
+        assert_eq!(Dbc::from_armored(&armored)?, vec![dbc.clone()]);
+
+        // Several Dbcs, pasted into an email with CRLF line endings.
+        let many = Dbc::to_armored_many(&[dbc.clone(), other_dbc.clone()])?;
+        let email = format!(
+            "Hi,\r\n\r\nhere you go:\r\n{}\r\nCheers",
+            many.replace('\n', "\r\n")
+        );
+        assert_eq!(Dbc::from_armored(&email)?, vec![dbc, other_dbc]);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_or_corrupted_armor_is_reported() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, _) = generate_dbc_and_its_main_key(100, &mut rng)?;
+        let armored = dbc.to_armored()?;
+        let lines: Vec<&str> = armored.lines().collect();
+
+        // Cut off part way through the body.
+        let cut = lines[..lines.len() / 2].join("\n");
+        assert!(matches!(
+            Dbc::from_armored(&cut),
+            Err(Error::ArmorTruncated(_))
+        ));
+        // A line of the body lost.
+        let mut missing_line = lines.clone();
+        let _ = missing_line.remove(6);
+        assert!(matches!(
+            Dbc::from_armored(&missing_line.join("\n")),
+            Err(Error::ArmorTruncated(_)) | Err(Error::ArmorCorrupted(_))
+        ));
+
+        // A character of the body changed.
+        let mut mangled = lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let c = if mangled[6].starts_with('A') {
+            "B"
+        } else {
+            "A"
+        };
+        mangled[6].replace_range(0..1, c);
+        assert!(matches!(
+            Dbc::from_armored(&mangled.join("\n")),
+            Err(Error::ArmorCorrupted(_))
+        ));
+
+        // Another version of the format.
+        let future = armored.replace("Version: 1", "Version: 2");
+        assert_eq!(
+            Dbc::from_armored(&future),
+            Err(Error::UnsupportedArmorVersion("2".to_string()))
+        );
+
+        assert!(matches!(
+            Dbc::from_armored(&dbc.to_hex()?),
+            Err(Error::ArmorCorrupted(_))
+        ));
+        Ok(())
+    }
+}
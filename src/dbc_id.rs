@@ -20,7 +20,7 @@ use crate::rand::{distributions::Standard, Rng, RngCore};
 pub type DerivationIndex = [u8; 32];
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct DbcId(PublicKey);
 
 impl DbcId {
@@ -32,6 +32,10 @@ impl DbcId {
         self.0.to_bytes()
     }
 
+    pub(crate) fn public_key(&self) -> PublicKey {
+        self.0
+    }
+
     /// Returns `true` if the signature matches the message.
     pub fn verify<M: AsRef<[u8]>>(&self, sig: &blsttc::Signature, msg: M) -> bool {
         self.0.verify(sig, msg)
@@ -73,6 +77,12 @@ impl DerivedKey {
     pub(crate) fn sign(&self, msg: &[u8]) -> blsttc::Signature {
         self.0.sign(msg)
     }
+
+    /// Represent as bytes. Only for the test vectors, as these are secret key bytes.
+    #[cfg(test)]
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes().to_vec()
+    }
 }
 
 /// This is a source that a specific DbcId can be derived from,
@@ -82,9 +92,9 @@ impl DerivedKey {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Copy, Clone)]
 pub struct DbcIdSource {
-    ///
+    /// The address that the DbcId is derived from.
     pub public_address: PublicAddress,
-    ///
+    /// The index used when deriving the DbcId from the address.
     pub derivation_index: DerivationIndex,
 }
 
@@ -110,7 +120,7 @@ impl DbcIdSource {
 /// The recipient can then use the received DerivationIndex to generate the DerivedKey
 /// corresponding to that DbcId, and thus unlock the value of the Dbc by using that DerivedKey.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Copy, Debug, PartialEq, Eq, Ord, PartialOrd, Clone)]
+#[derive(Copy, PartialEq, Eq, Ord, PartialOrd, Clone)]
 pub struct PublicAddress(PublicKey);
 
 impl PublicAddress {
@@ -145,6 +155,11 @@ impl PublicAddress {
         self.0.encrypt(derivation_index)
     }
 
+    /// Encrypt a RevealedAmount to this address, for its holder to see.
+    pub(crate) fn encrypt_amount(&self, revealed_amount: &RevealedAmount) -> Ciphertext {
+        self.0.encrypt(revealed_amount.to_bytes())
+    }
+
     pub fn to_bytes(self) -> [u8; PK_SIZE] {
         self.0.to_bytes()
     }
@@ -157,10 +172,11 @@ impl PublicAddress {
 /// The secret MainKey has a static PublicAddress, which
 /// is shared with others in order to receive payments.
 /// With this MainKey, new DerivedKey:DbcId pairs can be generated.
+#[derive(Debug, Clone)]
 pub struct MainKey(SerdeSecret<SecretKey>);
 
 impl MainKey {
-    ///
+    /// Create a MainKey from a SecretKey.
     pub fn new(secret_key: SecretKey) -> Self {
         Self(SerdeSecret(secret_key))
     }
@@ -203,6 +219,18 @@ impl MainKey {
         Ok(index)
     }
 
+    /// Decrypt a RevealedAmount encrypted to the PublicAddress of this MainKey.
+    pub(crate) fn decrypt_amount(
+        &self,
+        revealed_amount_cipher: &Ciphertext,
+    ) -> Result<RevealedAmount> {
+        let bytes = self
+            .0
+            .decrypt(revealed_amount_cipher)
+            .ok_or(Error::DecryptionBySecretKeyFailed)?;
+        RevealedAmount::from_bytes_ref(&bytes)
+    }
+
     /// Derive the key - the DerivedKey - corresponding to a DbcId
     /// which was also derived using the same DerivationIndex.
     ///
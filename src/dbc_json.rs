@@ -0,0 +1,505 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! A human readable JSON representation of Dbcs, txs, spends and ciphers.
+//!
+//! The serde derives of those types give raw byte arrays, which are of no use to a
+//! reader. This representation uses named keys, with keys, signatures, hashes and
+//! blinded amounts in hex, and ciphertexts and range proofs, which are long, in base64.
+//! Each document carries the `version` of this schema, which is not the version of
+//! the protocol that a tx or a spend is of. A `DbcTransaction`:
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "tx_version": 2,
+//!   "hash": "<hex, 32 bytes: the hash of the tx, checked on decoding>",
+//!   "network": "mainnet" | "testnet",
+//!   "inputs": [
+//!     {
+//!       "dbc_id": "<hex, 48 bytes>",
+//!       "blinded_amount": "<hex, 32 bytes: compressed ristretto point>",
+//!       "signature": "<hex, 96 bytes>" | null
+//!     }
+//!   ],
+//!   "outputs": [
+//!     {
+//!       "dbc_id": "<hex, 48 bytes>",
+//!       "blinded_amount": "<hex, 32 bytes>",
+//!       "range_proof": "<base64>"
+//!     }
+//!   ],
+//!   "aggregated_signature": "<hex, 96 bytes>" | null
+//! }
+//! ```
+//!
+//! A `SignedSpend`, where both txs are as above, without their `version`:
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "spend_version": 1,
+//!   "dbc_id": "<hex>",
+//!   "spent_tx": { ... },
+//!   "reason": "<hex, 32 bytes>",
+//!   "blinded_amount": "<hex>",
+//!   "dbc_creation_tx": { ... },
+//!   "derived_key_sig": "<hex, 96 bytes>"
+//! }
+//! ```
+//!
+//! `DbcCiphers`:
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "public_address": "<hex, 48 bytes>",
+//!   "derivation_index_cipher": "<base64>",
+//!   "revealed_amount_cipher": "<base64>"
+//! }
+//! ```
+//!
+//! And a `Dbc`, holding the others without their `version`:
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "id": "<hex, 48 bytes>",
+//!   "src_tx": { ... },
+//!   "ciphers": { ... },
+//!   "signed_spends": [ { ... } ]
+//! }
+//! ```
+//!
+//! Decoding a document gives back exactly the value that was encoded.
+
+use crate::{
+    transaction::{BlindedInput, BlindedOutput},
+    BlindedAmount, Ciphertext, Dbc, DbcCiphers, DbcId, DbcTransaction, Error, Hash, NetworkId,
+    PublicAddress, PublicKey, Result, Signature, SignedSpend, Spend,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bulletproofs::RangeProof;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// The version of the JSON schema written by this crate.
+/// Version 2 added the `network` and `tx_version` of txs, and the `spend_version` of spends.
+pub const JSON_SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    #[serde(flatten)]
+    value: T,
+}
+
+#[derive(Serialize, Deserialize)]
+struct InputJson {
+    dbc_id: String,
+    blinded_amount: String,
+    signature: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OutputJson {
+    dbc_id: String,
+    blinded_amount: String,
+    range_proof: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TxJson {
+    tx_version: u16,
+    hash: String,
+    network: NetworkId,
+    inputs: Vec<InputJson>,
+    outputs: Vec<OutputJson>,
+    aggregated_signature: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedSpendJson {
+    spend_version: u16,
+    dbc_id: String,
+    spent_tx: TxJson,
+    reason: String,
+    blinded_amount: String,
+    dbc_creation_tx: TxJson,
+    derived_key_sig: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CiphersJson {
+    public_address: String,
+    derivation_index_cipher: String,
+    revealed_amount_cipher: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DbcJson {
+    id: String,
+    src_tx: TxJson,
+    ciphers: CiphersJson,
+    signed_spends: Vec<SignedSpendJson>,
+}
+
+/// A type with a JSON representation.
+trait ToJson: Sized {
+    type Json: Serialize + DeserializeOwned;
+
+    fn to_repr(&self) -> Self::Json;
+
+    fn from_repr(json: Self::Json) -> Result<Self>;
+
+    fn encode(&self) -> Result<String> {
+        let document = Versioned {
+            version: JSON_SCHEMA_VERSION,
+            value: self.to_repr(),
+        };
+        serde_json::to_string_pretty(&document)
+            .map_err(|e| Error::JsonEncodingFailed(e.to_string()))
+    }
+
+    fn decode(json: &str) -> Result<Self> {
+        let document: Versioned<Self::Json> =
+            serde_json::from_str(json).map_err(|e| Error::JsonDecodingFailed(e.to_string()))?;
+        if document.version != JSON_SCHEMA_VERSION {
+            return Err(Error::JsonDecodingFailed(format!(
+                "unsupported schema version {}",
+                document.version
+            )));
+        }
+        Self::from_repr(document.value)
+    }
+}
+
+impl ToJson for DbcTransaction {
+    type Json = TxJson;
+
+    fn to_repr(&self) -> TxJson {
+        TxJson {
+            tx_version: self.version,
+            hash: self.hash().to_hex(),
+            network: self.network,
+            inputs: self
+                .inputs
+                .iter()
+                .map(|i| InputJson {
+                    dbc_id: hex::encode(i.dbc_id.to_bytes()),
+                    blinded_amount: encode_blinded_amount(&i.blinded_amount),
+                    signature: i.signature.as_ref().map(|s| hex::encode(s.to_bytes())),
+                })
+                .collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|o| OutputJson {
+                    dbc_id: hex::encode(o.dbc_id().to_bytes()),
+                    blinded_amount: encode_blinded_amount(&o.blinded_amount()),
+                    range_proof: BASE64.encode(o.range_proof().to_bytes()),
+                })
+                .collect(),
+            aggregated_signature: self
+                .aggregated_signature
+                .as_ref()
+                .map(|s| hex::encode(s.to_bytes())),
+        }
+    }
+
+    fn from_repr(json: TxJson) -> Result<Self> {
+        let inputs = json
+            .inputs
+            .into_iter()
+            .map(|i| {
+                Ok(BlindedInput {
+                    dbc_id: decode_dbc_id("input dbc_id", &i.dbc_id)?,
+                    blinded_amount: decode_blinded_amount(
+                        "input blinded_amount",
+                        &i.blinded_amount,
+                    )?,
+                    signature: i
+                        .signature
+                        .map(|s| decode_signature("input signature", &s))
+                        .transpose()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let outputs = json
+            .outputs
+            .into_iter()
+            .map(|o| {
+                let range_proof =
+                    RangeProof::from_bytes(&decode_base64("range_proof", &o.range_proof)?)
+                        .map_err(|e| invalid("range_proof", e))?;
+                Ok(BlindedOutput::from_parts(
+                    decode_dbc_id("output dbc_id", &o.dbc_id)?,
+                    range_proof,
+                    decode_blinded_amount("output blinded_amount", &o.blinded_amount)?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+        let tx = DbcTransaction {
+            version: json.tx_version,
+            inputs,
+            outputs,
+            aggregated_signature: json
+                .aggregated_signature
+                .map(|s| decode_signature("aggregated_signature", &s))
+                .transpose()?,
+            network: json.network,
+        };
+
+        if tx.hash() != decode_hash("tx hash", &json.hash)? {
+            return Err(invalid("tx hash", "it is not the hash of the tx"));
+        }
+        Ok(tx)
+    }
+}
+
+impl ToJson for SignedSpend {
+    type Json = SignedSpendJson;
+
+    fn to_repr(&self) -> SignedSpendJson {
+        SignedSpendJson {
+            spend_version: self.spend.version,
+            dbc_id: hex::encode(self.spend.dbc_id.to_bytes()),
+            spent_tx: self.spend.spent_tx.to_repr(),
+            reason: self.spend.reason.to_hex(),
+            blinded_amount: encode_blinded_amount(&self.spend.blinded_amount),
+            dbc_creation_tx: self.spend.dbc_creation_tx.to_repr(),
+            derived_key_sig: hex::encode(self.derived_key_sig.to_bytes()),
+        }
+    }
+
+    fn from_repr(json: SignedSpendJson) -> Result<Self> {
+        Ok(SignedSpend {
+            spend: Spend {
+                version: json.spend_version,
+                dbc_id: decode_dbc_id("spend dbc_id", &json.dbc_id)?,
+                spent_tx: DbcTransaction::from_repr(json.spent_tx)?,
+                reason: decode_hash("reason", &json.reason)?,
+                blinded_amount: decode_blinded_amount(
+                    "spend blinded_amount",
+                    &json.blinded_amount,
+                )?,
+                dbc_creation_tx: DbcTransaction::from_repr(json.dbc_creation_tx)?,
+            },
+            derived_key_sig: decode_signature("derived_key_sig", &json.derived_key_sig)?,
+        })
+    }
+}
+
+impl ToJson for DbcCiphers {
+    type Json = CiphersJson;
+
+    fn to_repr(&self) -> CiphersJson {
+        CiphersJson {
+            public_address: hex::encode(self.public_address.to_bytes()),
+            derivation_index_cipher: BASE64.encode(self.derivation_index_cipher.to_bytes()),
+            revealed_amount_cipher: BASE64.encode(self.revealed_amount_cipher.to_bytes()),
+        }
+    }
+
+    fn from_repr(json: CiphersJson) -> Result<Self> {
+        Ok(DbcCiphers {
+            public_address: PublicAddress::new(decode_public_key(
+                "public_address",
+                &json.public_address,
+            )?),
+            derivation_index_cipher: decode_ciphertext(
+                "derivation_index_cipher",
+                &json.derivation_index_cipher,
+            )?,
+            revealed_amount_cipher: decode_ciphertext(
+                "revealed_amount_cipher",
+                &json.revealed_amount_cipher,
+            )?,
+        })
+    }
+}
+
+impl ToJson for Dbc {
+    type Json = DbcJson;
+
+    fn to_repr(&self) -> DbcJson {
+        DbcJson {
+            id: hex::encode(self.id.to_bytes()),
+            src_tx: self.src_tx.to_repr(),
+            ciphers: self.ciphers.to_repr(),
+            signed_spends: self.signed_spends.iter().map(ToJson::to_repr).collect(),
+        }
+    }
+
+    fn from_repr(json: DbcJson) -> Result<Self> {
+        Ok(Dbc {
+            id: decode_dbc_id("id", &json.id)?,
+            src_tx: DbcTransaction::from_repr(json.src_tx)?,
+            ciphers: DbcCiphers::from_repr(json.ciphers)?,
+            signed_spends: json
+                .signed_spends
+                .into_iter()
+                .map(SignedSpend::from_repr)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl Dbc {
+    /// Represent this Dbc as JSON, see the `dbc_json` module for the schema.
+    pub fn to_json(&self) -> Result<String> {
+        self.encode()
+    }
+
+    /// Decode a Dbc from its JSON representation, see `to_json()`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::decode(json)
+    }
+}
+
+impl DbcTransaction {
+    /// Represent this tx as JSON, see the `dbc_json` module for the schema.
+    pub fn to_json(&self) -> Result<String> {
+        self.encode()
+    }
+
+    /// Decode a tx from its JSON representation, see `to_json()`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::decode(json)
+    }
+}
+
+impl SignedSpend {
+    /// Represent this SignedSpend as JSON, see the `dbc_json` module for the schema.
+    pub fn to_json(&self) -> Result<String> {
+        self.encode()
+    }
+
+    /// Decode a SignedSpend from its JSON representation, see `to_json()`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::decode(json)
+    }
+}
+
+impl DbcCiphers {
+    /// Represent these ciphers as JSON, see the `dbc_json` module for the schema.
+    pub fn to_json(&self) -> Result<String> {
+        self.encode()
+    }
+
+    /// Decode ciphers from their JSON representation, see `to_json()`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::decode(json)
+    }
+}
+
+fn invalid(field: &str, reason: impl std::fmt::Display) -> Error {
+    Error::JsonDecodingFailed(format!("invalid {field}: {reason}"))
+}
+
+fn encode_blinded_amount(blinded_amount: &BlindedAmount) -> String {
+    hex::encode(blinded_amount.compress().to_bytes())
+}
+
+fn decode_hex<const N: usize>(field: &str, hex: &str) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(hex, &mut bytes).map_err(|e| invalid(field, e))?;
+    Ok(bytes)
+}
+
+fn decode_base64(field: &str, base64: &str) -> Result<Vec<u8>> {
+    BASE64.decode(base64).map_err(|e| invalid(field, e))
+}
+
+fn decode_public_key(field: &str, hex: &str) -> Result<PublicKey> {
+    PublicKey::from_bytes(decode_hex(field, hex)?).map_err(|e| invalid(field, e))
+}
+
+fn decode_dbc_id(field: &str, hex: &str) -> Result<DbcId> {
+    Ok(DbcId::new(decode_public_key(field, hex)?))
+}
+
+fn decode_signature(field: &str, hex: &str) -> Result<Signature> {
+    Signature::from_bytes(decode_hex(field, hex)?).map_err(|e| invalid(field, e))
+}
+
+fn decode_hash(field: &str, hex: &str) -> Result<Hash> {
+    Ok(Hash::from(decode_hex::<32>(field, hex)?))
+}
+
+fn decode_blinded_amount(field: &str, hex: &str) -> Result<BlindedAmount> {
+    CompressedRistretto(decode_hex(field, hex)?)
+        .decompress()
+        .ok_or_else(|| invalid(field, "not a point"))
+}
+
+fn decode_ciphertext(field: &str, base64: &str) -> Result<Ciphertext> {
+    Ciphertext::from_bytes(&decode_base64(field, base64)?).map_err(|e| invalid(field, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbc::tests::generate_dbc_and_its_main_key;
+
+    #[test]
+    fn json_round_trips() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, main_key) = generate_dbc_and_its_main_key(100, &mut rng)?;
+
+        let json = dbc.to_json()?;
+        let decoded = Dbc::from_json(&json)?;
+        assert_eq!(decoded, dbc);
+        assert_eq!(decoded.hash(), dbc.hash());
+        decoded.verify(&main_key)?;
+
+        assert_eq!(
+            DbcTransaction::from_json(&dbc.src_tx.to_json()?)?,
+            dbc.src_tx
+        );
+        assert_eq!(DbcCiphers::from_json(&dbc.ciphers.to_json()?)?, dbc.ciphers);
+        for signed_spend in dbc.signed_spends.iter() {
+            assert_eq!(
+                &SignedSpend::from_json(&signed_spend.to_json()?)?,
+                signed_spend
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn json_is_readable() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, _) = generate_dbc_and_its_main_key(100, &mut rng)?;
+
+        let value: serde_json::Value =
+            serde_json::from_str(&dbc.to_json()?).expect("Failed to parse json");
+        assert_eq!(value["version"], JSON_SCHEMA_VERSION);
+        assert_eq!(value["id"], hex::encode(dbc.id().to_bytes()));
+        assert_eq!(value["src_tx"]["hash"], dbc.src_tx.hash().to_hex());
+        assert!(value["src_tx"].get("version").is_none());
+        assert!(value["ciphers"]["revealed_amount_cipher"].is_string());
+
+        // The tx hash is checked.
+        let mut tampered = value.clone();
+        tampered["src_tx"]["hash"] = Hash::default().to_hex().into();
+        assert!(matches!(
+            Dbc::from_json(&tampered.to_string()),
+            Err(Error::JsonDecodingFailed(_))
+        ));
+        // As is the version.
+        let mut future = value;
+        future["version"] = (JSON_SCHEMA_VERSION + 1).into();
+        assert!(matches!(
+            Dbc::from_json(&future.to_string()),
+            Err(Error::JsonDecodingFailed(_))
+        ));
+        Ok(())
+    }
+}
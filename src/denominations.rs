@@ -0,0 +1,244 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    rand::{CryptoRng, RngCore},
+    Dbc, DbcIdSource, DerivedKey, Error, PublicAddress, Result, Token, TransactionBuilder,
+};
+
+/// The default limit on the number of outputs an amount is split into.
+pub const DEFAULT_MAX_DENOMINATION_OUTPUTS: usize = 64;
+
+/// A series of standard amounts that payments are split into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denominations {
+    /// 1, 2, 4, 8, ... nano tokens.
+    PowersOfTwo,
+    /// 1, 2, 5, 10, 20, 50, ... nano tokens.
+    OneTwoFive,
+}
+
+impl Denominations {
+    /// The denominations of the series, from the largest down.
+    fn descending(self) -> Vec<u64> {
+        let mut denominations: Vec<u64> = match self {
+            Self::PowersOfTwo => (0..u64::BITS).map(|exp| 1 << exp).collect(),
+            Self::OneTwoFive => (0..u64::MAX.ilog10() + 1)
+                .flat_map(|exp| [1, 2, 5].map(|m| 10u64.pow(exp).checked_mul(m)))
+                .flatten()
+                .collect(),
+        };
+        denominations.reverse();
+        denominations
+    }
+}
+
+/// Splits an amount into outputs of standard denominations.
+///
+/// Paying exactly 1.234 tokens creates an output of that amount, and a change output,
+/// which together are easily told apart from other txs. Split into standard
+/// denominations, each to its own DbcId, the outputs of a payment look like those
+/// of any other payment.
+///
+/// Each output gets a fresh `DbcIdSource` of the recipient's `PublicAddress`, so the
+/// outputs can be passed to `TransactionBuilder::add_outputs()` as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DenominationSplitter {
+    denominations: Denominations,
+    max_outputs: usize,
+}
+
+impl DenominationSplitter {
+    /// Create a splitter into the given denominations, with the default output limit.
+    pub fn new(denominations: Denominations) -> Self {
+        Self {
+            denominations,
+            max_outputs: DEFAULT_MAX_DENOMINATION_OUTPUTS,
+        }
+    }
+
+    /// Set the limit on the number of outputs an amount is split into.
+    pub fn max_outputs(mut self, max_outputs: usize) -> Self {
+        self.max_outputs = max_outputs;
+        self
+    }
+
+    /// Split the amount into the fewest denominations adding up to it, largest first.
+    ///
+    /// Fails if that takes more outputs than the limit. A zero amount gives no outputs.
+    pub fn split(&self, amount: Token) -> Result<Vec<Token>> {
+        let mut remaining = amount.as_nano();
+        let mut amounts = vec![];
+        for denomination in self.denominations.descending() {
+            let count = remaining / denomination;
+            remaining %= denomination;
+            amounts.extend((0..count).map(|_| Token::from_nano(denomination)));
+            if amounts.len() > self.max_outputs {
+                return Err(Error::TooManyDenominationOutputs {
+                    amount,
+                    max_outputs: self.max_outputs,
+                });
+            }
+        }
+        Ok(amounts)
+    }
+
+    /// Split the amount, and give each part a new DbcId of the `PublicAddress`, for
+    /// `TransactionBuilder::add_outputs()`.
+    pub fn outputs(
+        &self,
+        amount: Token,
+        public_address: &PublicAddress,
+        rng: &mut impl RngCore,
+    ) -> Result<Vec<(Token, DbcIdSource)>> {
+        Ok(self
+            .split(amount)?
+            .into_iter()
+            .map(|amount| (amount, public_address.random_dbc_id_src(rng)))
+            .collect())
+    }
+
+    /// Spend received Dbcs into as few Dbcs of standard denominations as adds up to
+    /// their total, to the given `PublicAddress`.
+    ///
+    /// Receiving payments split into denominations leaves a recipient with many small
+    /// Dbcs. This consolidates them, without creating an amount which stands out.
+    pub fn consolidate(
+        &self,
+        dbcs: &[(Dbc, DerivedKey)],
+        public_address: &PublicAddress,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<TransactionBuilder> {
+        let builder = TransactionBuilder::default().add_input_dbcs(dbcs)?;
+        let outputs = self.outputs(builder.inputs_amount_sum(), public_address, rng)?;
+        Ok(builder.add_outputs(outputs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock,
+        tests::{TinyInt, TinyVec},
+        Hash, MainKey,
+    };
+    use quickcheck::QuickCheck;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn prop_split_adds_up_to_the_amount(amount: u64) -> Result<()> {
+        for denominations in [Denominations::PowersOfTwo, Denominations::OneTwoFive] {
+            let splitter = DenominationSplitter::new(denominations);
+            let amounts = splitter.split(Token::from_nano(amount))?;
+
+            assert_eq!(
+                amounts.iter().map(|a| a.as_nano() as u128).sum::<u128>(),
+                amount as u128
+            );
+            assert!(amounts.windows(2).all(|w| w[0] >= w[1]));
+            let standard = denominations.descending();
+            assert!(amounts.iter().all(|a| standard.contains(&a.as_nano())));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn split_into_fewest_denominations() -> Result<()> {
+        let nanos = |amounts: Vec<Token>| amounts.iter().map(|a| a.as_nano()).collect::<Vec<_>>();
+
+        let splitter = DenominationSplitter::new(Denominations::OneTwoFive);
+        assert_eq!(
+            nanos(splitter.split(Token::from_nano(1_234))?),
+            vec![1_000, 200, 20, 10, 2, 2]
+        );
+        assert!(splitter.split(Token::zero())?.is_empty());
+
+        let splitter = DenominationSplitter::new(Denominations::PowersOfTwo);
+        assert_eq!(nanos(splitter.split(Token::from_nano(13))?), vec![8, 4, 1]);
+        assert_eq!(splitter.split(Token::from_nano(u64::MAX))?.len(), 64);
+
+        let splitter = splitter.max_outputs(2);
+        assert_eq!(
+            splitter.split(Token::from_nano(13)),
+            Err(Error::TooManyDenominationOutputs {
+                amount: Token::from_nano(13),
+                max_outputs: 2
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn prop_pay_and_consolidate() {
+        fn prop(payments: TinyVec<TinyInt>) -> Result<()> {
+            let mut rng = crate::rng::from_seed([0u8; 32]);
+            let (mut spentbook_node, genesis_dbc, genesis_material, _) =
+                mock::GenesisBuilder::init_genesis_single(&mut rng)?;
+            let splitter = DenominationSplitter::new(Denominations::OneTwoFive);
+
+            // Pay the recipient each amount, split into denominations.
+            let recipient = MainKey::random_from_rng(&mut rng);
+            let payments: Vec<u64> = payments
+                .into_iter()
+                .map(|p| p.coerce::<u64>() * 3)
+                .collect();
+            let mut builder = TransactionBuilder::default().add_input_dbc(
+                &genesis_dbc,
+                &genesis_dbc.derived_key(&genesis_material.main_key)?,
+            )?;
+            for payment in payments.iter() {
+                builder = builder.add_outputs(splitter.outputs(
+                    Token::from_nano(*payment),
+                    &recipient.public_address(),
+                    &mut rng,
+                )?);
+            }
+            let change = mock::GenesisMaterial::GENESIS_AMOUNT - payments.iter().sum::<u64>();
+            let change_dbc_id_src = genesis_material.main_key.random_dbc_id_src(&mut rng);
+            let dbc_builder = builder
+                .add_output(Token::from_nano(change), change_dbc_id_src)
+                .build(Hash::default(), &mut rng)?;
+            spentbook_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
+
+            let received: Vec<(Dbc, DerivedKey)> = dbc_builder
+                .build()?
+                .into_iter()
+                .filter(|(dbc, _)| dbc.id() != change_dbc_id_src.dbc_id())
+                .map(|(dbc, _)| {
+                    let derived_key = dbc.derived_key(&recipient)?;
+                    Ok((dbc, derived_key))
+                })
+                .collect::<Result<_>>()?;
+            if received.is_empty() {
+                return Ok(());
+            }
+
+            // The recipient consolidates what they received.
+            let total: u64 = payments.iter().sum();
+            let consolidated = splitter
+                .consolidate(&received, &recipient.public_address(), &mut rng)?
+                .build(Hash::default(), &mut rng)?;
+            spentbook_node.log_tx(&consolidated.spent_tx, &consolidated.signed_spends)?;
+            let dbcs = consolidated.build()?;
+
+            assert_eq!(dbcs.len(), splitter.split(Token::from_nano(total))?.len());
+            assert!(dbcs.len() <= received.len());
+            assert_eq!(dbcs.iter().map(|(_, a)| a.value()).sum::<u64>(), total);
+            for (dbc, _) in dbcs.iter() {
+                dbc.verify(&recipient)?;
+            }
+            Ok(())
+        }
+
+        // Each case proves a range proof per denomination paid, up to a few dozen, twice.
+        QuickCheck::new()
+            .tests(40)
+            .quickcheck(prop as fn(TinyVec<TinyInt>) -> Result<()>);
+    }
+}
@@ -28,6 +28,23 @@ pub enum Error {
     /// Failed to parse a `Token` from a string.
     #[error("Failed to parse: {0}")]
     FailedToParseToken(String),
+    /// Splitting an amount into denominations takes more outputs than allowed.
+    #[error("Splitting {amount} into denominations takes more than {max_outputs} outputs.")]
+    TooManyDenominationOutputs {
+        amount: crate::Token,
+        max_outputs: usize,
+    },
+    /// Failed to parse a `Token` with a `TokenFormat`.
+    #[error("Failed to parse token amount: {0}")]
+    TokenParse(#[from] crate::TokenParseError),
+
+    /// Failed to parse a `PublicAddress` or `DbcId` string.
+    #[error("Failed to parse address: {0}")]
+    AddressParse(#[from] crate::AddressParseError),
+
+    /// Failed to parse a `PaymentUri`, or to pay it.
+    #[error("Invalid payment URI: {0}")]
+    PaymentUri(#[from] crate::PaymentUriError),
 
     #[error("Failed signature check.")]
     FailedSignature,
@@ -35,6 +52,9 @@ pub enum Error {
     #[error("Invalid Spend Signature for {0:?}")]
     InvalidSpendSignature(DbcId),
 
+    #[error("The spend of {dbc_id:?} is of version {version}, which is not supported.")]
+    UnsupportedSpendVersion { dbc_id: DbcId, version: u16 },
+
     #[error("Transaction hash does not match the transaction signed by spentbook.")]
     InvalidTransactionHash,
 
@@ -44,6 +64,15 @@ pub enum Error {
         dbc_creation_tx_hash: crate::Hash,
     },
 
+    #[error("The spend of {dbc_id:?} was rejected: {error}")]
+    SpendRejected { dbc_id: DbcId, error: Box<Error> },
+
+    #[error("Expected a tx of the {expected} network, but it is of the {found} network.")]
+    NetworkMismatch {
+        expected: crate::NetworkId,
+        found: crate::NetworkId,
+    },
+
     #[error("Dbc ciphers are not present in transaction outputs.")]
     DbcCiphersNotPresentInTransactionOutput,
 
@@ -93,6 +122,60 @@ pub enum Error {
     #[error("Could not serialize DBC to hex: {0}")]
     HexSerializationFailed(String),
 
+    #[error("Could not encode DBC in its compact form: {0}")]
+    CompactEncodingFailed(String),
+
+    #[error("Could not decode DBC from its compact form: {0}")]
+    CompactDecodingFailed(String),
+
+    #[error("Could not decode the hex of a Dbc of a previous release: {0}")]
+    LegacyDecodingFailed(String),
+
+    #[error("The Dbc of a previous release can not be migrated: {0}")]
+    IncompatibleLegacyDbc(String),
+
+    #[error("Could not encode to JSON: {0}")]
+    JsonEncodingFailed(String),
+
+    #[error("Could not decode from JSON: {0}")]
+    JsonDecodingFailed(String),
+
+    #[error("The armored DBC text is truncated: {0}")]
+    ArmorTruncated(String),
+
+    #[error("The armored DBC text is corrupted: {0}")]
+    ArmorCorrupted(String),
+
+    #[error("Unsupported version of the armored DBC format: {0}")]
+    UnsupportedArmorVersion(String),
+
+    #[error("The amount is not within the range.")]
+    AmountNotInRange,
+
+    #[error("An amount range proof needs a lower or an upper bound.")]
+    UnboundedAmountRange,
+
+    #[error("The amount range proof is not valid.")]
+    InvalidAmountRangeProof,
+
+    #[error("A reserves proof needs at least one Dbc and a minimum amount.")]
+    EmptyReservesProof,
+
+    #[error("A Dbc appears more than once in the reserves proof.")]
+    DbcIdNotUniqueInReserves,
+
+    #[error("Invalid reserves signature for {0:?}")]
+    InvalidReservesSignature(DbcId),
+
+    #[error("The reserves Dbc {0:?} has been spent.")]
+    ReservesDbcSpent(DbcId),
+
+    #[error("The input {0:?} of the src_tx of a reserves Dbc was not spent in that tx.")]
+    ReservesInputNotSpentInSrcTx(DbcId),
+
+    #[error("Invalid amount disclosure signature for {0:?}")]
+    InvalidAmountDisclosureSignature(DbcId),
+
     #[error("Bls error: {0}")]
     Blsttc(#[from] blsttc::error::Error),
 
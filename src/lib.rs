@@ -8,26 +8,51 @@
 
 #![allow(clippy::result_large_err)]
 
+mod address;
+mod amount_disclosure;
 mod blst;
 mod builder;
 mod dbc;
+#[cfg(feature = "serde")]
+mod dbc_armor;
 mod dbc_ciphers;
+#[cfg(feature = "serde")]
+mod dbc_compact;
 mod dbc_id;
+mod denominations;
 mod error;
+mod lineage;
+#[cfg(feature = "serde")]
+mod migration;
+mod network;
+mod parallel;
+mod payment_uri;
+mod reserves;
 mod signed_spend;
 mod spentbook;
 mod token;
+mod token_format;
 mod transaction;
 mod verification;
+mod verification_report;
 
+#[cfg(all(test, feature = "serde"))]
+mod test_vectors;
+
+#[cfg(feature = "json")]
+pub mod dbc_json;
 #[cfg(feature = "mock")]
 pub mod mock;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 // re-export crates used in our public API
 pub use blsttc::{self, rand, Ciphertext, PublicKey, PublicKeySet, Signature, SignatureShare};
 pub use bulletproofs::PedersenGens;
 
 pub use crate::{
+    address::AddressParseError,
+    amount_disclosure::AmountDisclosure,
     blst::{BlindedAmount, BlindingFactor},
     builder::{DbcBuilder, OutputIdSources, TransactionBuilder},
     dbc::Dbc,
@@ -36,14 +61,31 @@ pub use crate::{
         random_derivation_index, DbcId, DbcIdSource, DerivationIndex, DerivedKey, MainKey,
         PublicAddress,
     },
+    denominations::{DenominationSplitter, Denominations, DEFAULT_MAX_DENOMINATION_OUTPUTS},
     error::{Error, Result},
+    lineage::{Lineage, LineageTx, LineageVerifier, TransactionSource},
+    network::NetworkId,
+    payment_uri::{PaymentUri, PaymentUriError},
+    reserves::{ReservesEntry, ReservesProof},
     signed_spend::{SignedSpend, Spend},
     token::Token,
+    token_format::{TokenFormat, TokenParseError, TokenUnit},
     transaction::{
-        Amount, BlindedInput, BlindedOutput, DbcTransaction, InputHistory, Output, RevealedAmount,
-        RevealedInput, RevealedTx,
+        Amount, AmountRangeProof, BlindedInput, BlindedOutput, DbcTransaction, InputHistory,
+        Output, RevealedAmount, RevealedInput, RevealedTx,
+    },
+    verification::{
+        get_blinded_amounts_from_transaction, TransactionVerifier, VerifiedTxCache,
+        DEFAULT_VERIFIED_TX_CACHE_CAPACITY,
     },
-    verification::{get_blinded_amounts_from_transaction, TransactionVerifier},
+    verification_report::{Check, CheckResult, CheckSubject, VerificationReport},
+};
+
+#[cfg(feature = "serde")]
+pub use crate::{
+    dbc_armor::DBC_ARMOR_VERSION,
+    migration::{LegacyDbc, LegacyLayout},
+    token_format::token_decimal,
 };
 
 #[cfg(feature = "serde")]
@@ -0,0 +1,220 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{Dbc, DbcId, DbcTransaction, Error, Hash, Result, SignedSpend, TransactionVerifier};
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// A source of the SignedSpends of past transactions, such as the spentbook.
+pub trait TransactionSource {
+    /// The SignedSpends of every input of the tx with the given hash,
+    /// or None if they are not all known.
+    fn signed_spends(&self, spent_tx_hash: &Hash) -> Option<BTreeSet<SignedSpend>>;
+}
+
+impl TransactionSource for BTreeMap<Hash, BTreeSet<SignedSpend>> {
+    fn signed_spends(&self, spent_tx_hash: &Hash) -> Option<BTreeSet<SignedSpend>> {
+        self.get(spent_tx_hash).cloned()
+    }
+}
+
+/// A tx of a verified lineage.
+#[derive(Debug, Clone)]
+pub struct LineageTx {
+    pub tx: DbcTransaction,
+    /// The SignedSpends of the inputs of the tx.
+    pub signed_spends: BTreeSet<SignedSpend>,
+    /// The number of hops back from the tx the Dbc was created in, which is at depth 0.
+    pub depth: usize,
+}
+
+/// The verified ancestry of a Dbc.
+#[derive(Debug, Clone, Default)]
+pub struct Lineage {
+    /// The verified txs, by hash.
+    pub txs: BTreeMap<Hash, LineageTx>,
+    /// True if every branch of the lineage was followed all the way to genesis.
+    pub reached_genesis: bool,
+}
+
+impl Lineage {
+    /// The hashes of the txs that created the inputs of the given tx.
+    pub fn parents(&self, tx_hash: &Hash) -> BTreeSet<Hash> {
+        self.txs
+            .get(tx_hash)
+            .map(|t| {
+                t.signed_spends
+                    .iter()
+                    .map(|s| s.dbc_creation_tx_hash())
+                    .filter(|h| self.txs.contains_key(h))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Verifies the lineage of a Dbc, and not only the tx it was created in.
+///
+/// `Dbc::verify()` checks the src_tx of a Dbc, and the SignedSpends of its inputs.
+/// This walks further back, through the `dbc_creation_tx` of each of those spends,
+/// verifying every ancestor tx along with the SignedSpends of its own inputs,
+/// which are obtained from a TransactionSource.
+///
+/// For each hop, the blinded amount of the spent input must also match the
+/// blinded amount of the output that created it.
+///
+/// The walk stops at the genesis DbcId, or at the configured maximum depth.
+#[derive(Debug, Clone)]
+pub struct LineageVerifier {
+    genesis_dbc_id: DbcId,
+    max_depth: Option<usize>,
+}
+
+impl LineageVerifier {
+    /// Create a verifier following the lineage back to the given genesis DbcId.
+    pub fn new(genesis_dbc_id: DbcId) -> Self {
+        Self {
+            genesis_dbc_id,
+            max_depth: None,
+        }
+    }
+
+    /// Do not follow the lineage further back than the given number of hops.
+    /// At depth 0, only the tx the Dbc was created in is verified.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Verify the lineage of a Dbc.
+    pub fn verify(&self, dbc: &Dbc, source: &impl TransactionSource) -> Result<Lineage> {
+        self.verify_tx(&dbc.src_tx, &dbc.signed_spends, source)
+    }
+
+    /// Verify the lineage of a tx, given the SignedSpends of its inputs.
+    pub fn verify_tx(
+        &self,
+        tx: &DbcTransaction,
+        signed_spends: &BTreeSet<SignedSpend>,
+        source: &impl TransactionSource,
+    ) -> Result<Lineage> {
+        let mut lineage = Lineage {
+            txs: Default::default(),
+            reached_genesis: true,
+        };
+        let mut queue = VecDeque::from([(tx.clone(), signed_spends.clone(), 0)]);
+
+        while let Some((tx, signed_spends, depth)) = queue.pop_front() {
+            let tx_hash = tx.hash();
+            if lineage.txs.contains_key(&tx_hash) {
+                continue;
+            }
+            TransactionVerifier::verify(&tx, &signed_spends)?;
+
+            for signed_spend in signed_spends.iter() {
+                let dbc_id = *signed_spend.dbc_id();
+                if dbc_id == self.genesis_dbc_id {
+                    continue;
+                }
+                if self.max_depth.map(|max| depth >= max).unwrap_or(false) {
+                    lineage.reached_genesis = false;
+                    continue;
+                }
+
+                // The input must have been created with the amount it is spent with.
+                let parent_tx = &signed_spend.spend.dbc_creation_tx;
+                let output = parent_tx
+                    .outputs
+                    .iter()
+                    .find(|o| o.dbc_id() == &dbc_id)
+                    .ok_or(Error::BlindedOutputNotFound)?;
+                if &output.blinded_amount() != signed_spend.blinded_amount() {
+                    return Err(Error::BlindedAmountsDoNotMatch);
+                }
+
+                let parent_tx_hash = parent_tx.hash();
+                if lineage.txs.contains_key(&parent_tx_hash) {
+                    continue;
+                }
+                let parent_spends = source.signed_spends(&parent_tx_hash).ok_or(
+                    Error::MissingSpentSrcTransaction {
+                        dbc_id,
+                        dbc_creation_tx_hash: parent_tx_hash,
+                    },
+                )?;
+                queue.push_back((parent_tx.clone(), parent_spends, depth + 1));
+            }
+
+            lineage.txs.insert(
+                tx_hash,
+                LineageTx {
+                    tx,
+                    signed_spends,
+                    depth,
+                },
+            );
+        }
+
+        Ok(lineage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, Token, TransactionBuilder};
+
+    #[test]
+    fn lineage_is_verified_back_to_genesis() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (mut spentbook_node, _, dbc, main_key) =
+            crate::dbc::tests::generate_dbc_and_its_main_key(100, &mut rng)?;
+
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(&dbc, &dbc.derived_key(&main_key)?)?
+            .add_output(Token::from_nano(100), main_key.random_dbc_id_src(&mut rng))
+            .build(Hash::default(), &mut rng)?;
+        spentbook_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
+        let (new_dbc, _) = dbc_builder.build()?.remove(0);
+
+        let genesis_dbc_id = mock::GenesisMaterial::default().input_dbc_id;
+        let verifier = LineageVerifier::new(genesis_dbc_id);
+
+        // new_dbc <- dbc <- genesis dbc <- genesis input
+        let lineage = verifier.verify(&new_dbc, &spentbook_node)?;
+        assert!(lineage.reached_genesis);
+        assert_eq!(lineage.txs.len(), 3);
+        let depths: Vec<usize> = lineage.txs.values().map(|t| t.depth).collect();
+        assert_eq!(depths.iter().max(), Some(&2));
+        assert_eq!(
+            lineage.parents(&new_dbc.src_tx.hash()),
+            BTreeSet::from([dbc.src_tx.hash()])
+        );
+
+        let lineage = verifier
+            .clone()
+            .max_depth(1)
+            .verify(&new_dbc, &spentbook_node)?;
+        assert!(!lineage.reached_genesis);
+        assert_eq!(lineage.txs.len(), 2);
+
+        // Without the spends of the ancestors, only the src_tx can be verified.
+        let no_source = BTreeMap::new();
+        let lineage = verifier.clone().max_depth(0).verify(&new_dbc, &no_source)?;
+        assert_eq!(lineage.txs.len(), 1);
+        assert_eq!(
+            verifier.verify(&new_dbc, &no_source).map(|_| ()),
+            Err(Error::MissingSpentSrcTransaction {
+                dbc_id: dbc.id(),
+                dbc_creation_tx_hash: dbc.src_tx.hash(),
+            })
+        );
+
+        Ok(())
+    }
+}
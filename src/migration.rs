@@ -0,0 +1,331 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Decoding of the Dbcs of previous releases, and their migration to the current `Dbc`.
+//!
+//! `Dbc::to_hex()` is the bincode encoding of the Dbc, so its layout changes with the
+//! fields of the Dbc and of the types it holds. The layouts decoded here are:
+//!
+//! - 18.0.0 to 19.0.1: a Spend holds the hash of the tx that created the spent Dbc.
+//! - 19.1.0: the Spend holds that tx, each input carries its own signature, and
+//!   neither txs nor spends have a version or a network.
+//!
+//! A Dbc of 19.1.0 is migrated to txs and spends of `DbcTransaction::LEGACY_VERSION`,
+//! whose signatures and range proofs are those of 19.1.0, and which are only valid on
+//! the mainnet. A Dbc of 18.0.0 to 19.0.1 can not be migrated: its spends are signed
+//! over the hash of the tx that created the spent Dbc, not over the tx itself, which
+//! every spend now commits to.
+
+use crate::{
+    BlindedAmount, BlindedInput, BlindedOutput, Dbc, DbcCiphers, DbcId, DbcTransaction, Error,
+    Hash, NetworkId, Result, Signature, SignedSpend, Spend, TransactionVerifier,
+};
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::fmt;
+
+/// A layout of `Dbc::to_hex()` of a previous release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyLayout {
+    /// Releases 18.0.0 to 19.0.1.
+    V18,
+    /// Release 19.1.0.
+    V19_1,
+}
+
+impl fmt::Display for LegacyLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V18 => write!(f, "18.0.0 - 19.0.1"),
+            Self::V19_1 => write!(f, "19.1.0"),
+        }
+    }
+}
+
+/// A Dbc decoded from the hex of a previous release.
+#[derive(Debug, Clone)]
+pub struct LegacyDbc(Layout);
+
+#[derive(Debug, Clone)]
+enum Layout {
+    V18(Box<DbcLayout<SpendV18>>),
+    V19_1(Box<DbcLayout<SpendV19_1>>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DbcLayout<S> {
+    id: DbcId,
+    src_tx: TxLayout,
+    ciphers: DbcCiphers,
+    signed_spends: Vec<SignedSpendLayout<S>>,
+}
+
+/// The tx of 18.0.0 to 19.1.0.
+#[derive(Debug, Clone, Deserialize)]
+struct TxLayout {
+    inputs: Vec<InputLayout>,
+    outputs: Vec<BlindedOutput>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct InputLayout {
+    dbc_id: DbcId,
+    blinded_amount: BlindedAmount,
+    signature: Signature,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SignedSpendLayout<S> {
+    spend: S,
+    derived_key_sig: Signature,
+}
+
+// Only decoded to tell the layout apart, as these spends can not be migrated.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+struct SpendV18 {
+    dbc_id: DbcId,
+    spent_tx: TxLayout,
+    reason: Hash,
+    blinded_amount: BlindedAmount,
+    dbc_creation_tx_hash: Hash,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SpendV19_1 {
+    dbc_id: DbcId,
+    spent_tx: TxLayout,
+    reason: Hash,
+    blinded_amount: BlindedAmount,
+    dbc_creation_tx: TxLayout,
+}
+
+impl LegacyDbc {
+    /// Decode the hex of a Dbc of a previous release, as given by its `Dbc::to_hex()`.
+    /// The layouts are tried from the most recent one.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let mut bytes = hex::decode(hex).map_err(|e| Error::LegacyDecodingFailed(e.to_string()))?;
+        bytes.reverse();
+
+        if let Ok(dbc) = decode(&bytes) {
+            return Ok(Self(Layout::V19_1(Box::new(dbc))));
+        }
+        decode(&bytes)
+            .map(|dbc| Self(Layout::V18(Box::new(dbc))))
+            .map_err(|e| Error::LegacyDecodingFailed(format!("not of a known layout: {e}")))
+    }
+
+    /// The layout that the Dbc was decoded from.
+    pub fn layout(&self) -> LegacyLayout {
+        match &self.0 {
+            Layout::V18(_) => LegacyLayout::V18,
+            Layout::V19_1(_) => LegacyLayout::V19_1,
+        }
+    }
+
+    /// The id of the Dbc.
+    pub fn id(&self) -> DbcId {
+        match &self.0 {
+            Layout::V18(dbc) => dbc.id,
+            Layout::V19_1(dbc) => dbc.id,
+        }
+    }
+
+    /// Convert to the current `Dbc`, which is verified like any other tx and spends.
+    /// It fails with `Error::IncompatibleLegacyDbc` if the layout can not be migrated,
+    /// or if the migrated Dbc is not valid.
+    pub fn migrate(self) -> Result<Dbc> {
+        let dbc = match self.0 {
+            Layout::V18(_) => {
+                return Err(Error::IncompatibleLegacyDbc(format!(
+                    "the spends of release {} are not signed over the tx that created \
+                     the spent Dbc, which every spend must commit to",
+                    LegacyLayout::V18
+                )))
+            }
+            Layout::V19_1(dbc) => Dbc {
+                id: dbc.id,
+                src_tx: dbc.src_tx.migrate(),
+                ciphers: dbc.ciphers,
+                signed_spends: dbc
+                    .signed_spends
+                    .into_iter()
+                    .map(|s| SignedSpend {
+                        spend: Spend {
+                            version: Spend::LEGACY_VERSION,
+                            dbc_id: s.spend.dbc_id,
+                            spent_tx: s.spend.spent_tx.migrate(),
+                            reason: s.spend.reason,
+                            blinded_amount: s.spend.blinded_amount,
+                            dbc_creation_tx: s.spend.dbc_creation_tx.migrate(),
+                        },
+                        derived_key_sig: s.derived_key_sig,
+                    })
+                    .collect(),
+            },
+        };
+
+        TransactionVerifier::verify(&dbc.src_tx, &dbc.signed_spends).map_err(|e| {
+            Error::IncompatibleLegacyDbc(format!("the migrated Dbc is not valid: {e}"))
+        })?;
+        Ok(dbc)
+    }
+}
+
+impl TxLayout {
+    fn migrate(self) -> DbcTransaction {
+        DbcTransaction {
+            version: DbcTransaction::LEGACY_VERSION,
+            inputs: self
+                .inputs
+                .into_iter()
+                .map(|i| BlindedInput {
+                    dbc_id: i.dbc_id,
+                    blinded_amount: i.blinded_amount,
+                    signature: Some(i.signature),
+                })
+                .collect(),
+            outputs: self.outputs,
+            aggregated_signature: None,
+            network: NetworkId::Mainnet,
+        }
+    }
+}
+
+impl Dbc {
+    /// Decode the hex of a Dbc of a previous release, and migrate it to the current `Dbc`.
+    /// See `LegacyDbc` to tell the layout of a Dbc that can not be migrated.
+    pub fn from_legacy_hex(hex: &str) -> Result<Self> {
+        LegacyDbc::from_hex(hex)?.migrate()
+    }
+}
+
+/// Decode with the options of `bincode::deserialize()`, but without trailing bytes,
+/// so that a layout is not mistaken for a shorter one.
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> bincode::Result<T> {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MainKey, Token, TransactionBuilder};
+
+    use blsttc::SecretKey;
+    use std::convert::TryInto;
+
+    /// A Dbc written by a previous release, with the key it was sent to.
+    #[derive(Deserialize)]
+    struct Fixture {
+        main_key: String,
+        amount: u64,
+        dbc: String,
+    }
+
+    fn fixture(json: &str) -> Result<(Fixture, MainKey)> {
+        let fixture: Fixture =
+            serde_json::from_str(json).map_err(|e| Error::LegacyDecodingFailed(e.to_string()))?;
+        let key_bytes: [u8; 32] = hex::decode(&fixture.main_key)
+            .map_err(|e| Error::LegacyDecodingFailed(e.to_string()))?
+            .try_into()
+            .map_err(|_| Error::LegacyDecodingFailed("main key length".to_string()))?;
+        let main_key = MainKey::new(SecretKey::from_bytes(key_bytes)?);
+        Ok((fixture, main_key))
+    }
+
+    #[test]
+    fn a_dbc_of_19_1_is_migrated() -> Result<()> {
+        let (fixture, main_key) = fixture(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_vectors/legacy/dbc_19_1.json"
+        )))?;
+        assert!(Dbc::from_hex(&fixture.dbc).is_err());
+
+        let legacy_dbc = LegacyDbc::from_hex(&fixture.dbc)?;
+        assert_eq!(legacy_dbc.layout(), LegacyLayout::V19_1);
+        let id = legacy_dbc.id();
+        let dbc = legacy_dbc.migrate()?;
+        assert_eq!(dbc.id(), id);
+        assert_eq!(dbc.src_tx.version, DbcTransaction::LEGACY_VERSION);
+        dbc.verify(&main_key)?;
+        let derived_key = dbc.derived_key(&main_key)?;
+        assert_eq!(dbc.revealed_amount(&derived_key)?.value(), fixture.amount);
+
+        // The migrated Dbc is spent in a tx of the current version.
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(&dbc, &derived_key)?
+            .add_output(
+                Token::from_nano(fixture.amount),
+                main_key.random_dbc_id_src(&mut rng),
+            )
+            .build(Hash::default(), &mut rng)?;
+        assert_eq!(dbc_builder.spent_tx.version, DbcTransaction::VERSION);
+        TransactionVerifier::verify(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
+
+        // A legacy tx commits to no network, so it is only valid on the mainnet.
+        let mut testnet_dbc = dbc;
+        testnet_dbc.src_tx.network = NetworkId::Testnet;
+        assert_eq!(
+            testnet_dbc.src_tx.verify_version(),
+            Err(crate::transaction::Error::LegacyTxNotOnMainnet(
+                NetworkId::Testnet
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn a_dbc_of_18_0_to_19_0_is_decoded_but_not_migrated() -> Result<()> {
+        for json in [
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test_vectors/legacy/dbc_18_0.json"
+            )),
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test_vectors/legacy/dbc_19_0.json"
+            )),
+        ] {
+            let (fixture, _) = fixture(json)?;
+
+            let legacy_dbc = LegacyDbc::from_hex(&fixture.dbc)?;
+            assert_eq!(legacy_dbc.layout(), LegacyLayout::V18);
+            assert!(matches!(
+                legacy_dbc.migrate(),
+                Err(Error::IncompatibleLegacyDbc(_))
+            ));
+            assert!(matches!(
+                Dbc::from_legacy_hex(&fixture.dbc),
+                Err(Error::IncompatibleLegacyDbc(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn a_dbc_of_an_unknown_layout_is_not_decoded() -> Result<()> {
+        assert!(matches!(
+            LegacyDbc::from_hex("00ff"),
+            Err(Error::LegacyDecodingFailed(_))
+        ));
+
+        // Nor is a Dbc of the current layout.
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, _) = crate::dbc::tests::generate_dbc_and_its_main_key(100, &mut rng)?;
+        assert!(matches!(
+            Dbc::from_legacy_hex(&dbc.to_hex()?),
+            Err(Error::LegacyDecodingFailed(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{mock_spentbook::check_network, GenesisMaterial};
+
+use crate::{
+    transaction::{BlindedOutput, DbcTransaction},
+    BlindedAmount, DbcId, Error, Hash, NetworkId, Result, SignedSpend,
+};
+
+use bulletproofs::PedersenGens;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// The number of independently locked partitions of the DbcId index.
+const NUM_SHARDS: usize = 16;
+
+/// A SpentbookNode that can be shared between, and logged to from, many threads at once.
+///
+/// The DbcId --> tx_hash index is split into shards, each behind its own lock, which
+/// is only held while an input is being marked as spent. Tx verification, which is
+/// by far the most expensive step, is done without holding any lock.
+///
+/// This guarantees that of several conflicting spends of the same DbcId racing each other,
+/// exactly one is logged, while spends of different DbcIds proceed in parallel.
+///
+/// The outputs of a tx are made available to later spends while the lock of the
+/// spent input is held, so that the outputs of a tx which lost a race are never added.
+#[derive(Debug)]
+pub struct ConcurrentSpentbook {
+    genesis: (DbcId, BlindedAmount),
+    shards: Vec<Mutex<BTreeMap<DbcId, Hash>>>,
+    transactions: RwLock<HashMap<Hash, DbcTransaction>>,
+    outputs_by_input_id: RwLock<BTreeMap<DbcId, BlindedOutput>>,
+    network: NetworkId,
+}
+
+impl Default for ConcurrentSpentbook {
+    fn default() -> Self {
+        Self::for_network(NetworkId::default())
+    }
+}
+
+impl ConcurrentSpentbook {
+    /// Create a ConcurrentSpentbook which only logs the txs of the given network.
+    pub fn for_network(network: NetworkId) -> Self {
+        let genesis_material = GenesisMaterial::for_network(network);
+        let blinded_amount = genesis_material.genesis_tx.inputs[0]
+            .input
+            .revealed_amount()
+            .blinded_amount(&PedersenGens::default());
+
+        Self {
+            genesis: (genesis_material.input_dbc_id, blinded_amount),
+            shards: (0..NUM_SHARDS).map(|_| Default::default()).collect(),
+            transactions: Default::default(),
+            outputs_by_input_id: Default::default(),
+            network,
+        }
+    }
+
+    pub fn is_spent(&self, dbc_id: &DbcId) -> bool {
+        self.shard(dbc_id).contains_key(dbc_id)
+    }
+
+    /// The hash of the tx the given DbcId was spent in, if it has been spent.
+    pub fn spent_tx_hash(&self, dbc_id: &DbcId) -> Option<Hash> {
+        self.shard(dbc_id).get(dbc_id).copied()
+    }
+
+    /// The tx with the given hash, if it has been logged.
+    pub fn transaction(&self, tx_hash: &Hash) -> Option<DbcTransaction> {
+        read(&self.transactions).get(tx_hash).cloned()
+    }
+
+    pub fn log_spent(&self, spent_tx: &DbcTransaction, signed_spend: &SignedSpend) -> Result<()> {
+        let input_id = signed_spend.dbc_id();
+        let tx_hash = spent_tx.hash();
+
+        if tx_hash != signed_spend.spent_tx_hash() {
+            return Err(Error::InvalidTransactionHash);
+        }
+        check_network(self.network, spent_tx)?;
+
+        // Input amounts are not available in spentbook for genesis transaction.
+        let (genesis_dbc_id, genesis_blinded_amount) = &self.genesis;
+        let tx_blinded_amounts: Vec<BlindedAmount> = if input_id == genesis_dbc_id {
+            vec![*genesis_blinded_amount]
+        } else {
+            let outputs_by_input_id = read(&self.outputs_by_input_id);
+            spent_tx
+                .inputs
+                .iter()
+                .map(|input| {
+                    let input_id = input.dbc_id();
+                    match outputs_by_input_id.get(&input_id) {
+                        Some(p) => Ok(p.blinded_amount()),
+                        None => Err(Error::MissingAmountForDbcId(input_id)),
+                    }
+                })
+                .collect::<Result<_>>()?
+        };
+
+        // Do not permit invalid tx to be logged.
+        // This is done before taking any lock, so that it can run in parallel.
+        spent_tx.verify(&tx_blinded_amounts)?;
+
+        let mut shard = self.shard(input_id);
+        let existing_tx_hash = *shard.entry(*input_id).or_insert(tx_hash);
+        if existing_tx_hash != tx_hash {
+            return Err(crate::mock::Error::DbcAlreadySpent.into());
+        }
+
+        // Still holding the shard lock, so that no other spend of this input
+        // can be reported as logged before the outputs are available.
+        let mut outputs_by_input_id = write(&self.outputs_by_input_id);
+        for output in spent_tx.outputs.iter() {
+            outputs_by_input_id
+                .entry(*output.dbc_id())
+                .or_insert_with(|| output.clone());
+        }
+        write(&self.transactions)
+            .entry(tx_hash)
+            .or_insert_with(|| spent_tx.clone());
+
+        Ok(())
+    }
+
+    fn shard(&self, dbc_id: &DbcId) -> MutexGuard<'_, BTreeMap<DbcId, Hash>> {
+        // The first byte of a compressed bls public key holds flags,
+        // so we use the last byte to pick a shard.
+        let idx = dbc_id.to_bytes()[blsttc::PK_SIZE - 1] as usize % NUM_SHARDS;
+        self.shards[idx]
+            .lock()
+            .expect("A thread panicked while holding a spentbook shard lock")
+    }
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read()
+        .expect("A thread panicked while holding a spentbook lock")
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write()
+        .expect("A thread panicked while holding a spentbook lock")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock,
+        rand::{Rng, SeedableRng},
+        Dbc, DbcBuilder, MainKey, Token, TransactionBuilder,
+    };
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    const NUM_THREADS: usize = 8;
+    const NUM_ROUNDS: u64 = 20;
+
+    fn spentbook_with_genesis(genesis_dbc: &Dbc) -> Result<ConcurrentSpentbook> {
+        let spentbook = ConcurrentSpentbook::default();
+        for signed_spend in genesis_dbc.signed_spends.iter() {
+            spentbook.log_spent(&genesis_dbc.src_tx, signed_spend)?;
+        }
+        Ok(spentbook)
+    }
+
+    // Runs each job on its own thread, all released at once, and each
+    // yielding a random number of times first, to vary the interleavings.
+    fn race<T: Send + 'static>(seed: u64, jobs: Vec<Box<dyn FnOnce() -> T + Send>>) -> Vec<T> {
+        let barrier = Arc::new(Barrier::new(jobs.len()));
+        let handles: Vec<_> = jobs
+            .into_iter()
+            .enumerate()
+            .map(|(i, job)| {
+                let barrier = barrier.clone();
+                let mut rng = crate::rand::rngs::StdRng::seed_from_u64(seed * 1000 + i as u64);
+                thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..rng.gen_range(0..50) {
+                        thread::yield_now();
+                    }
+                    job()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    }
+
+    #[test]
+    fn exactly_one_of_racing_conflicting_spends_wins() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis_single(&mut rng)?;
+        let derived_key = genesis_dbc.derived_key(&genesis_material.main_key)?;
+
+        // Each builder spends the genesis dbc to a different recipient.
+        let dbc_builders: Vec<DbcBuilder> = (0..NUM_THREADS)
+            .map(|_| {
+                TransactionBuilder::default()
+                    .add_input_dbc(&genesis_dbc, &derived_key)?
+                    .add_output(
+                        Token::from_nano(mock::GenesisMaterial::GENESIS_AMOUNT),
+                        MainKey::random_from_rng(&mut rng).random_dbc_id_src(&mut rng),
+                    )
+                    .build(Hash::default(), &mut rng)
+            })
+            .collect::<Result<_>>()?;
+
+        for round in 0..NUM_ROUNDS {
+            let spentbook = Arc::new(spentbook_with_genesis(&genesis_dbc)?);
+            let jobs = dbc_builders
+                .iter()
+                .cloned()
+                .map(|dbc_builder| {
+                    let spentbook = spentbook.clone();
+                    Box::new(move || {
+                        let signed_spend = dbc_builder.signed_spends()[0].clone();
+                        spentbook
+                            .log_spent(&dbc_builder.spent_tx, &signed_spend)
+                            .map(|_| dbc_builder.spent_tx.hash())
+                    }) as Box<dyn FnOnce() -> _ + Send>
+                })
+                .collect();
+
+            let results: Vec<Result<Hash>> = race(round, jobs);
+            let winners: Vec<Hash> = results.iter().flatten().copied().collect();
+            assert_eq!(winners.len(), 1);
+            for result in results.iter().filter(|r| r.is_err()) {
+                assert_eq!(result, &Err(Error::Mock(mock::Error::DbcAlreadySpent)));
+            }
+
+            // Only the outputs of the winning tx can be spent later.
+            assert_eq!(spentbook.spent_tx_hash(&genesis_dbc.id()), Some(winners[0]));
+            for dbc_builder in dbc_builders.iter() {
+                let output_id = *dbc_builder.spent_tx.outputs[0].dbc_id();
+                let is_winner = dbc_builder.spent_tx.hash() == winners[0];
+                let is_known = read(&spentbook.outputs_by_input_id).contains_key(&output_id);
+                assert_eq!(is_known, is_winner);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn txs_of_another_network_are_rejected() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, genesis_dbc, ..) = mock::GenesisBuilder::default()
+            .network(NetworkId::Testnet)
+            .build(&mut rng)?;
+        let signed_spend = genesis_dbc.signed_spends.iter().next().unwrap();
+
+        // The genesis input is the same on every network, but its tx is not.
+        assert_eq!(
+            ConcurrentSpentbook::default().log_spent(&genesis_dbc.src_tx, signed_spend),
+            Err(Error::NetworkMismatch {
+                expected: NetworkId::Mainnet,
+                found: NetworkId::Testnet,
+            })
+        );
+        let spentbook = ConcurrentSpentbook::for_network(NetworkId::Testnet);
+        spentbook.log_spent(&genesis_dbc.src_tx, signed_spend)?;
+        assert!(spentbook.is_spent(signed_spend.dbc_id()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn non_conflicting_spends_are_all_logged() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis_single(&mut rng)?;
+        let spentbook = Arc::new(spentbook_with_genesis(&genesis_dbc)?);
+
+        // Split the genesis dbc into one dbc per thread.
+        let amount = mock::GenesisMaterial::GENESIS_AMOUNT / NUM_THREADS as u64;
+        let main_key = MainKey::random_from_rng(&mut rng);
+        let mut outputs: Vec<_> = (0..NUM_THREADS)
+            .map(|_| {
+                (
+                    Token::from_nano(amount),
+                    main_key.random_dbc_id_src(&mut rng),
+                )
+            })
+            .collect();
+        outputs[0].0 = Token::from_nano(
+            mock::GenesisMaterial::GENESIS_AMOUNT - amount * (NUM_THREADS as u64 - 1),
+        );
+        let derived_key = genesis_dbc.derived_key(&genesis_material.main_key)?;
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(&genesis_dbc, &derived_key)?
+            .add_outputs(outputs)
+            .build(Hash::default(), &mut rng)?;
+        for signed_spend in dbc_builder.signed_spends() {
+            spentbook.log_spent(&dbc_builder.spent_tx, signed_spend)?;
+        }
+
+        // Spend every one of them at once.
+        let dbc_builders: Vec<DbcBuilder> = dbc_builder
+            .build()?
+            .into_iter()
+            .map(|(dbc, revealed_amount)| {
+                let derived_key = dbc.derived_key(&main_key)?;
+                TransactionBuilder::default()
+                    .add_input_dbc(&dbc, &derived_key)?
+                    .add_output(
+                        Token::from_nano(revealed_amount.value()),
+                        main_key.random_dbc_id_src(&mut rng),
+                    )
+                    .build(Hash::default(), &mut rng)
+            })
+            .collect::<Result<_>>()?;
+
+        let jobs = dbc_builders
+            .iter()
+            .cloned()
+            .map(|dbc_builder| {
+                let spentbook = spentbook.clone();
+                Box::new(move || {
+                    let signed_spend = dbc_builder.signed_spends()[0].clone();
+                    spentbook.log_spent(&dbc_builder.spent_tx, &signed_spend)
+                }) as Box<dyn FnOnce() -> _ + Send>
+            })
+            .collect();
+
+        for result in race(0, jobs) {
+            assert_eq!(result, Ok(()));
+        }
+        for dbc_builder in dbc_builders.iter() {
+            let tx_hash = dbc_builder.spent_tx.hash();
+            assert_eq!(
+                spentbook.spent_tx_hash(dbc_builder.signed_spends()[0].dbc_id()),
+                Some(tx_hash)
+            );
+            assert!(spentbook.transaction(&tx_hash).is_some());
+        }
+
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@
 // under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
+use crate::DbcId;
 use thiserror::Error;
 
 #[cfg(feature = "serde")]
@@ -16,4 +17,16 @@ use serde::{Deserialize, Serialize};
 pub enum Error {
     #[error("Dbc has already been spent")]
     DbcAlreadySpent,
+    #[error("Spentbook log entry out of sequence: expected {expected}, found {found}")]
+    LogEntryOutOfSequence { expected: u64, found: u64 },
+    #[error("Spentbook log entry {0} does not chain to the previous entry")]
+    LogEntryPrevHashMismatch(u64),
+    #[error("Spentbook log entry {0} is missing a valid node signature")]
+    LogEntryInvalidSignature(u64),
+    #[error("Spentbook log entry {0} is not reproduced when replayed")]
+    LogEntryNotReplayed(u64),
+    #[error("The ancestry of the spend is deeper than a node learns")]
+    AncestryTooDeep,
+    #[error("The spends of {0:?} are not conflicting")]
+    NotADoubleSpend(DbcId),
 }
@@ -10,7 +10,7 @@ use super::GenesisMaterial;
 use crate::{
     mock,
     rand::{CryptoRng, RngCore},
-    Dbc, Hash, Result, RevealedAmount, Token, TransactionBuilder,
+    Dbc, Hash, NetworkId, Result, RevealedAmount, Token, TransactionBuilder,
 };
 
 /// A builder for initializing a set of N spentbooks and generating a
@@ -21,13 +21,22 @@ use crate::{
 #[derive(Default)]
 pub struct GenesisBuilder {
     pub spentbook_nodes: Vec<mock::SpentbookNode>,
+    pub network: NetworkId,
 }
 
 impl GenesisBuilder {
+    /// Sets the network of the genesis dbc, mainnet by default.
+    /// Spentbook nodes generated after this are of that network.
+    pub fn network(mut self, network: NetworkId) -> Self {
+        self.network = network;
+        self
+    }
+
     /// Generates a list of spentbook nodes and adds to the builder.
     pub fn gen_spentbook_nodes(mut self, num_nodes: usize) -> Self {
         for _ in 0..num_nodes {
-            self.spentbook_nodes.push(mock::SpentbookNode::default());
+            self.spentbook_nodes
+                .push(mock::SpentbookNode::for_network(self.network));
         }
         self
     }
@@ -49,8 +58,9 @@ impl GenesisBuilder {
         GenesisMaterial,
         RevealedAmount,
     )> {
-        let genesis_material = GenesisMaterial::default();
+        let genesis_material = GenesisMaterial::for_network(self.network);
         let dbc_builder = TransactionBuilder::default()
+            .network(self.network)
             .add_input(genesis_material.genesis_tx.inputs[0].clone())
             .add_output(
                 Token::from_nano(genesis_material.genesis_tx.outputs[0].amount),
@@ -58,11 +68,8 @@ impl GenesisBuilder {
             )
             .build(Hash::default(), rng)?;
 
-        let tx = &dbc_builder.spent_tx;
-        for signed_spend in dbc_builder.signed_spends() {
-            for spentbook_node in self.spentbook_nodes.iter_mut() {
-                spentbook_node.log_spent(tx, signed_spend)?;
-            }
+        for spentbook_node in self.spentbook_nodes.iter_mut() {
+            spentbook_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
         }
 
         let (genesis_dbc, revealed_amount) = dbc_builder.build()?.into_iter().next().unwrap();
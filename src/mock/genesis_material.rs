@@ -9,7 +9,7 @@
 use crate::{
     dbc_id::DbcIdSource,
     transaction::{Amount, InputHistory, Output, RevealedAmount, RevealedInput, RevealedTx},
-    DbcId, DbcTransaction, DerivedKey, MainKey,
+    DbcId, DbcTransaction, DerivedKey, MainKey, NetworkId,
 };
 use blsttc::IntoFr;
 
@@ -25,13 +25,11 @@ pub struct GenesisMaterial {
 impl GenesisMaterial {
     /// The Genesis DBC will mint all possible tokens.
     pub const GENESIS_AMOUNT: Amount = Amount::MAX; // aka 2^64
-}
 
-impl Default for GenesisMaterial {
-    /// generate the GenesisMaterial.
+    /// generate the GenesisMaterial of the network.
     ///
-    /// It uses GenesisMaterial::GENESIS_AMOUNT by default
-    fn default() -> Self {
+    /// It uses GenesisMaterial::GENESIS_AMOUNT
+    pub fn for_network(network: NetworkId) -> Self {
         // Make a secret key for the input of Genesis Tx. (fictional Dbc)
         // note that this is the derived key.
         // (we have no need for the main key)
@@ -62,8 +60,11 @@ impl Default for GenesisMaterial {
             // There is nothing in this transaction
             // since there was no tx before genesis.
             input_src_tx: DbcTransaction {
+                version: DbcTransaction::VERSION,
                 inputs: vec![],
                 outputs: vec![],
+                aggregated_signature: None,
+                network,
             },
         };
 
@@ -74,6 +75,7 @@ impl Default for GenesisMaterial {
                 output_derived_key.dbc_id(),
                 Self::GENESIS_AMOUNT,
             )],
+            network,
         };
 
         let output_dbc_id_src = DbcIdSource {
@@ -90,3 +92,10 @@ impl Default for GenesisMaterial {
         }
     }
 }
+
+impl Default for GenesisMaterial {
+    /// generate the GenesisMaterial of the mainnet.
+    fn default() -> Self {
+        Self::for_network(NetworkId::default())
+    }
+}
@@ -6,16 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::GenesisMaterial;
+use super::{spentbook_log, GenesisMaterial, SpentbookLogEntry};
 
 use crate::{
     dbc_id::PublicAddress,
-    transaction::{BlindedOutput, DbcTransaction},
-    BlindedAmount, DbcId, Error, Hash, Result, SignedSpend,
+    transaction::{self, BlindedOutput, DbcTransaction},
+    BlindedAmount, DbcId, Error, Hash, NetworkId, Result, SignedSpend, TransactionSource,
+    VerifiedTxCache,
 };
 
+use blsttc::SecretKey;
 use bulletproofs::PedersenGens;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// This is a mock SpentBook used for our test cases. A proper implementation
 /// will be distributed, persistent, and auditable.
@@ -39,6 +41,13 @@ use std::collections::{BTreeMap, HashMap};
 ///
 /// See the very first commit of this file For a naive impl that uses only
 /// a single map<public_key, tx>.
+///
+/// Every accepted spend is also appended to a hash-chained log, in the order
+/// it was logged. The log can be exported, verified end-to-end, and replayed
+/// into a new SpentbookNode. If the node has a key, each log entry is signed by it.
+///
+/// Txs that have been verified are kept in a bounded cache, so that a tx is
+/// verified once, and not once per input, when its spends are logged.
 #[derive(Debug, Clone)]
 pub struct SpentbookNode {
     pub id: PublicAddress,
@@ -46,11 +55,27 @@ pub struct SpentbookNode {
     pub dbc_ids: BTreeMap<DbcId, Hash>,
     pub outputs_by_input_id: BTreeMap<DbcId, BlindedOutput>,
     pub genesis: (DbcId, BlindedAmount),
+    pub log: Vec<SpentbookLogEntry>,
+    pub verified_txs: VerifiedTxCache,
+    pub network: NetworkId,
+    log_index_by_dbc_id: BTreeMap<DbcId, usize>,
+    key: Option<SecretKey>,
 }
 
 impl Default for SpentbookNode {
     fn default() -> Self {
-        let genesis_material = GenesisMaterial::default();
+        Self::for_network(NetworkId::default())
+    }
+}
+
+impl SpentbookNode {
+    /// The most generations of txs that are learned to log a spend whose input is unknown
+    /// to this node (see `learn_dbc_creation_tx()`).
+    pub const MAX_ANCESTRY_DEPTH: usize = 32;
+
+    /// Create a SpentbookNode which only logs the txs of the given network.
+    pub fn for_network(network: NetworkId) -> Self {
+        let genesis_material = GenesisMaterial::for_network(network);
         let blinded_amount = genesis_material.genesis_tx.inputs[0]
             .input
             .revealed_amount()
@@ -62,11 +87,75 @@ impl Default for SpentbookNode {
             dbc_ids: Default::default(),
             outputs_by_input_id: Default::default(),
             genesis: (genesis_material.input_dbc_id, blinded_amount),
+            log: Default::default(),
+            verified_txs: Default::default(),
+            network,
+            log_index_by_dbc_id: Default::default(),
+            key: None,
         }
     }
-}
 
-impl SpentbookNode {
+    /// Create a SpentbookNode which signs its log entries with the given key.
+    /// The id of the node is the public key of the given key.
+    pub fn with_key(key: SecretKey) -> Self {
+        Self {
+            id: PublicAddress::new(key.public_key()),
+            key: Some(key),
+            ..Default::default()
+        }
+    }
+
+    /// Rebuild a SpentbookNode by replaying the given log.
+    ///
+    /// The log is verified before it is replayed (see `spentbook_log::verify_log`),
+    /// and every spend is verified again as it is logged to the new node.
+    ///
+    /// The entries are kept as they were verified, with the signatures of the node that
+    /// logged them, whose id is then the id of the new node. The new node has no key, so
+    /// the entries it logs later are not signed.
+    ///
+    /// The new node is of the network of the logged txs, or of the default network if
+    /// the log is empty.
+    pub fn from_log(log: &[SpentbookLogEntry], signer: Option<&PublicAddress>) -> Result<Self> {
+        spentbook_log::verify_log(log, signer)?;
+
+        let network = log
+            .first()
+            .map(|entry| entry.spent_tx.network)
+            .unwrap_or_default();
+        let mut spentbook_node = Self::for_network(network);
+        if let Some(signer) = signer {
+            spentbook_node.id = *signer;
+        }
+        for entry in log {
+            spentbook_node.log_spent(&entry.spent_tx, &entry.signed_spend)?;
+            match spentbook_node.log.last_mut() {
+                Some(replayed) if replayed.seq == entry.seq && replayed.hash() == entry.hash() => {
+                    replayed.node_sig = entry.node_sig.clone();
+                }
+                _ => return Err(crate::mock::Error::LogEntryNotReplayed(entry.seq).into()),
+            }
+        }
+        Ok(spentbook_node)
+    }
+
+    /// Export the log of all spends, in the order they were logged.
+    pub fn export_log(&self) -> Vec<SpentbookLogEntry> {
+        self.log.clone()
+    }
+
+    /// The hash of the last entry of the log.
+    /// The default Hash if nothing has been logged yet.
+    pub fn log_head(&self) -> Hash {
+        self.log.last().map(|e| e.hash()).unwrap_or_default()
+    }
+
+    /// Verify the log of this node, returning the hash of its last entry.
+    pub fn verify_log(&self) -> Result<Hash> {
+        let signer = self.key.as_ref().map(|_| &self.id);
+        spentbook_log::verify_log(&self.log, signer)
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (&DbcId, &DbcTransaction)> + '_ {
         self.dbc_ids.iter().map(move |(k, h)| {
             (
@@ -84,10 +173,172 @@ impl SpentbookNode {
         self.dbc_ids.contains_key(dbc_id)
     }
 
+    /// Whether the given DbcId was logged as spent in the tx of the given hash.
+    pub fn is_spent_in(&self, dbc_id: &DbcId, tx_hash: Hash) -> bool {
+        self.dbc_ids.get(dbc_id) == Some(&tx_hash)
+    }
+
+    /// The SignedSpend that was logged for the given DbcId, if any.
+    pub fn signed_spend(&self, dbc_id: &DbcId) -> Option<&SignedSpend> {
+        self.log_index_by_dbc_id
+            .get(dbc_id)
+            .map(|idx| &self.log[*idx].signed_spend)
+    }
+
     pub fn log_spent(&mut self, tx: &DbcTransaction, signed_spend: &SignedSpend) -> Result<()> {
         self.log_spent_worker(tx, signed_spend, true)
     }
 
+    /// Log the spends of every input of a tx, or none of them.
+    ///
+    /// All spends are validated, and the tx verified, before any of them is logged.
+    /// If the tx can not be logged because of one of its inputs, the returned
+    /// `Error::SpendRejected` holds the DbcId of that input.
+    pub fn log_tx(
+        &mut self,
+        spent_tx: &DbcTransaction,
+        signed_spends: &BTreeSet<SignedSpend>,
+    ) -> Result<()> {
+        self.validate_tx(spent_tx, signed_spends, &[])?;
+        self.commit_tx(spent_tx.hash(), spent_tx, signed_spends)
+    }
+
+    /// Log a spend (see `log_spent()`), first learning the txs that created its input,
+    /// if that input is unknown to this node (see `learn_dbc_creation_tx()`).
+    ///
+    /// Nothing is logged unless the spend, and every tx learned for it, is valid.
+    pub fn log_spent_with_parents(
+        &mut self,
+        tx: &DbcTransaction,
+        signed_spend: &SignedSpend,
+        parent_spends: &BTreeSet<SignedSpend>,
+    ) -> Result<()> {
+        let mut staged = vec![];
+        self.stage_dbc_creation_tx(
+            signed_spend,
+            parent_spends,
+            Self::MAX_ANCESTRY_DEPTH,
+            &mut staged,
+        )?;
+        let tx_hash = self.validate_spend(tx, signed_spend, true, &staged)?;
+
+        self.commit_staged_txs(&staged)?;
+        self.commit_spend(tx_hash, tx, signed_spend)
+    }
+
+    /// Log the spends of every input of a tx, or none of them (see `log_tx()`), first learning
+    /// the txs that created any of its inputs unknown to this node (see `learn_dbc_creation_tx()`).
+    ///
+    /// Nothing is logged unless the tx, and every tx learned for it, is valid.
+    pub fn log_tx_with_parents(
+        &mut self,
+        spent_tx: &DbcTransaction,
+        signed_spends: &BTreeSet<SignedSpend>,
+        parent_spends: &BTreeSet<SignedSpend>,
+    ) -> Result<()> {
+        self.log_tx_with_ancestry(
+            spent_tx,
+            signed_spends,
+            parent_spends,
+            Self::MAX_ANCESTRY_DEPTH,
+        )
+    }
+
+    fn log_tx_with_ancestry(
+        &mut self,
+        spent_tx: &DbcTransaction,
+        signed_spends: &BTreeSet<SignedSpend>,
+        parent_spends: &BTreeSet<SignedSpend>,
+        max_depth: usize,
+    ) -> Result<()> {
+        let mut staged = vec![];
+        for signed_spend in signed_spends.iter() {
+            self.stage_dbc_creation_tx(signed_spend, parent_spends, max_depth, &mut staged)
+                .map_err(|error| Error::SpendRejected {
+                    dbc_id: *signed_spend.dbc_id(),
+                    error: Box::new(error),
+                })?;
+        }
+        self.validate_tx(spent_tx, signed_spends, &staged)?;
+
+        self.commit_staged_txs(&staged)?;
+        self.commit_tx(spent_tx.hash(), spent_tx, signed_spends)
+    }
+
+    /// Learn the outputs of the tx that created the input of a spend, i.e. its `dbc_creation_tx`,
+    /// so that a node which has not seen that tx can still log the spend.
+    ///
+    /// The parent tx is authenticated by the signature of the spend, which is made over its hash.
+    /// It is then logged like any other tx (see `log_tx()`), with the SignedSpends of its own
+    /// inputs, which are looked up in `parent_spends`, e.g. the `signed_spends` of the Dbc being
+    /// spent. So the amounts of its inputs must be known to this node, or be learned in turn
+    /// from the txs that created them. `parent_spends` must then hold the SignedSpends of all
+    /// those txs. At most `MAX_ANCESTRY_DEPTH` generations of txs are learned.
+    ///
+    /// Every learned tx is verified before any of them is logged. The inputs of the learned
+    /// txs are thereby logged as spent, and can not be spent again.
+    pub fn learn_dbc_creation_tx(
+        &mut self,
+        signed_spend: &SignedSpend,
+        parent_spends: &BTreeSet<SignedSpend>,
+    ) -> Result<()> {
+        let mut staged = vec![];
+        self.stage_dbc_creation_tx(
+            signed_spend,
+            parent_spends,
+            Self::MAX_ANCESTRY_DEPTH,
+            &mut staged,
+        )?;
+        self.commit_staged_txs(&staged)
+    }
+
+    // Validate the tx that created the input of the spend, and the txs it depends on in turn,
+    // unless the amount of the input is already known. Each validated tx is staged after the
+    // txs it depends on, so that they can all be committed in order. Nothing is committed here.
+    fn stage_dbc_creation_tx(
+        &mut self,
+        signed_spend: &SignedSpend,
+        parent_spends: &BTreeSet<SignedSpend>,
+        max_depth: usize,
+        staged: &mut Vec<StagedTx>,
+    ) -> Result<()> {
+        if self.blinded_amount(signed_spend.dbc_id(), staged).is_some() {
+            return Ok(());
+        }
+        if max_depth == 0 {
+            return Err(crate::mock::Error::AncestryTooDeep.into());
+        }
+
+        signed_spend.verify(signed_spend.spent_tx_hash())?;
+
+        let parent_tx = &signed_spend.spend.dbc_creation_tx;
+        if !parent_tx
+            .outputs
+            .iter()
+            .any(|o| o.dbc_id() == signed_spend.dbc_id())
+        {
+            return Err(Error::BlindedOutputNotFound);
+        }
+
+        let parent_tx_hash = parent_tx.hash();
+        let parent_tx_spends: BTreeSet<SignedSpend> = parent_spends
+            .iter()
+            .filter(|s| s.spent_tx_hash() == parent_tx_hash)
+            .cloned()
+            .collect();
+        for parent_tx_spend in parent_tx_spends.iter() {
+            self.stage_dbc_creation_tx(parent_tx_spend, parent_spends, max_depth - 1, staged)?;
+        }
+        self.validate_tx(parent_tx, &parent_tx_spends, staged)?;
+
+        staged.push(StagedTx {
+            hash: parent_tx_hash,
+            tx: parent_tx.clone(),
+            signed_spends: parent_tx_spends,
+        });
+        Ok(())
+    }
+
     // This is invalid behavior, however we provide this method for test cases
     // that need to write an invalid Tx to spentbook in order to test reissue
     // behavior.
@@ -100,12 +351,30 @@ impl SpentbookNode {
         self.log_spent_worker(spent_tx, signed_spend, false)
     }
 
+    /// Txs of other networks are not valid here, even if they are otherwise valid.
+    fn check_network(&self, spent_tx: &DbcTransaction) -> Result<()> {
+        check_network(self.network, spent_tx)
+    }
+
     fn log_spent_worker(
         &mut self,
         spent_tx: &DbcTransaction,
         signed_spend: &SignedSpend,
         verify_tx: bool,
     ) -> Result<()> {
+        let tx_hash = self.validate_spend(spent_tx, signed_spend, verify_tx, &[])?;
+        self.commit_spend(tx_hash, spent_tx, signed_spend)
+    }
+
+    // Check that a spend can be logged, given the txs staged before it.
+    // Returns the hash of the spent tx.
+    fn validate_spend(
+        &mut self,
+        spent_tx: &DbcTransaction,
+        signed_spend: &SignedSpend,
+        verify_tx: bool,
+        staged: &[StagedTx],
+    ) -> Result<Hash> {
         let input_id = signed_spend.dbc_id();
         let spent_tx_hash = signed_spend.spent_tx_hash();
         let tx_hash = spent_tx.hash();
@@ -113,47 +382,169 @@ impl SpentbookNode {
         if tx_hash != spent_tx_hash {
             return Err(Error::InvalidTransactionHash);
         }
+        self.check_network(spent_tx)?;
 
-        // If this is the very first tx logged and genesis dbc_id was not
-        // provided, then it becomes the genesis tx.
-        let (genesis_dbc_id, genesis_blinded_amount) = &self.genesis;
-
-        // Input amounts are not available in spentbook for genesis transaction.
-        let blinded_amounts_by_input_ids: Vec<(DbcId, BlindedAmount)> =
-            if input_id == genesis_dbc_id {
-                vec![(*input_id, *genesis_blinded_amount)]
-            } else {
-                spent_tx
-                    .inputs
-                    .iter()
-                    .map(|input| {
-                        // look up matching BlindedOutput
-                        let input_id = input.dbc_id();
-                        let blinded_output = self.outputs_by_input_id.get(&input_id);
-                        match blinded_output {
-                            Some(p) => Ok((input_id, p.blinded_amount())),
-                            None => Err(Error::MissingAmountForDbcId(input_id)),
-                        }
-                    })
-                    .collect::<Result<_>>()?
-            };
+        match self.spent_in(input_id, staged) {
+            Some(existing_tx_hash) if existing_tx_hash != tx_hash => {
+                return Err(crate::mock::Error::DbcAlreadySpent.into())
+            }
+            _ => {}
+        }
 
-        // Grab all blinded amounts, grouped by input PublicKey
+        // Grab all blinded amounts, in the order of the inputs.
         // Needed for Tx verification.
-        let tx_blinded_amounts: Vec<BlindedAmount> = blinded_amounts_by_input_ids
-            .into_iter()
-            .map(|(_, c)| c)
-            .collect();
+        let tx_blinded_amounts: Vec<BlindedAmount> = spent_tx
+            .inputs
+            .iter()
+            .map(|input| {
+                let input_id = input.dbc_id();
+                self.blinded_amount(&input_id, staged)
+                    .ok_or(Error::MissingAmountForDbcId(input_id))
+            })
+            .collect::<Result<_>>()?;
 
         if verify_tx {
             // Do not permit invalid tx to be logged.
-            spent_tx.verify(&tx_blinded_amounts)?;
+            // The tx is verified only once, not once per input.
+            self.verified_txs.verify(spent_tx, &tx_blinded_amounts)?;
         }
 
+        Ok(tx_hash)
+    }
+
+    // Check that the spends of every input of a tx can be logged, given the txs staged before it.
+    fn validate_tx(
+        &mut self,
+        spent_tx: &DbcTransaction,
+        signed_spends: &BTreeSet<SignedSpend>,
+        staged: &[StagedTx],
+    ) -> Result<()> {
+        if signed_spends.len() != spent_tx.inputs.len() {
+            return Err(Error::SignedSpendInputLenMismatch {
+                current: signed_spends.len(),
+                expected: spent_tx.inputs.len(),
+            });
+        }
+
+        self.check_network(spent_tx)?;
+        let tx_hash = spent_tx.hash();
+
+        let mut tx_blinded_amounts = Vec::with_capacity(spent_tx.inputs.len());
+        for input in spent_tx.inputs.iter() {
+            let input_id = input.dbc_id();
+            let rejected = |error: Error| Error::SpendRejected {
+                dbc_id: input_id,
+                error: Box::new(error),
+            };
+
+            let signed_spend = signed_spends
+                .iter()
+                .find(|s| s.dbc_id() == &input_id)
+                .ok_or_else(|| rejected(Error::SignedSpendInputIdMismatch))?;
+            signed_spend.verify(tx_hash).map_err(rejected)?;
+
+            match self.spent_in(&input_id, staged) {
+                Some(existing_tx_hash) if existing_tx_hash != tx_hash => {
+                    return Err(rejected(crate::mock::Error::DbcAlreadySpent.into()))
+                }
+                _ => {}
+            }
+
+            let blinded_amount = self
+                .blinded_amount(&input_id, staged)
+                .ok_or_else(|| rejected(Error::MissingAmountForDbcId(input_id)))?;
+            if input.blinded_amount != blinded_amount {
+                let error = transaction::Error::InvalidInputBlindedAmount;
+                return Err(rejected(error.into()));
+            }
+            tx_blinded_amounts.push(blinded_amount);
+        }
+
+        // Do not permit invalid tx to be logged.
+        if let Err(error) = self.verified_txs.verify(spent_tx, &tx_blinded_amounts) {
+            // Blame the input whose signature is invalid, if any.
+            let msg = spent_tx.serialize_tx();
+            for (input, blinded_amount) in spent_tx.inputs.iter().zip(tx_blinded_amounts) {
+                if let Err(error) = input.verify(&msg, blinded_amount) {
+                    return Err(Error::SpendRejected {
+                        dbc_id: input.dbc_id(),
+                        error: Box::new(error.into()),
+                    });
+                }
+            }
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    // The blinded amount of an input, if it is genesis, an output of a logged tx,
+    // or an output of a staged tx.
+    fn blinded_amount(&self, input_id: &DbcId, staged: &[StagedTx]) -> Option<BlindedAmount> {
+        // Input amounts are not available in spentbook for genesis transaction.
+        let (genesis_dbc_id, genesis_blinded_amount) = self.genesis;
+        if *input_id == genesis_dbc_id {
+            return Some(genesis_blinded_amount);
+        }
+        self.outputs_by_input_id
+            .get(input_id)
+            .or_else(|| {
+                staged
+                    .iter()
+                    .flat_map(|staged_tx| staged_tx.tx.outputs.iter())
+                    .find(|output| output.dbc_id() == input_id)
+            })
+            .map(|output| output.blinded_amount())
+    }
+
+    // The hash of the tx that an input was spent in, if it is logged or staged.
+    fn spent_in(&self, input_id: &DbcId, staged: &[StagedTx]) -> Option<Hash> {
+        self.dbc_ids.get(input_id).copied().or_else(|| {
+            staged
+                .iter()
+                .find(|staged_tx| staged_tx.tx.inputs.iter().any(|i| &i.dbc_id() == input_id))
+                .map(|staged_tx| staged_tx.hash)
+        })
+    }
+
+    fn commit_staged_txs(&mut self, staged: &[StagedTx]) -> Result<()> {
+        for staged_tx in staged {
+            self.commit_tx(staged_tx.hash, &staged_tx.tx, &staged_tx.signed_spends)?;
+        }
+        Ok(())
+    }
+
+    fn commit_tx(
+        &mut self,
+        tx_hash: Hash,
+        spent_tx: &DbcTransaction,
+        signed_spends: &BTreeSet<SignedSpend>,
+    ) -> Result<()> {
+        for signed_spend in signed_spends.iter() {
+            self.commit_spend(tx_hash, spent_tx, signed_spend)?;
+        }
+        Ok(())
+    }
+
+    // Mark the input of a verified spend as spent in the given tx,
+    // and make the outputs of the tx available to later spends.
+    fn commit_spend(
+        &mut self,
+        tx_hash: Hash,
+        spent_tx: &DbcTransaction,
+        signed_spend: &SignedSpend,
+    ) -> Result<()> {
+        let input_id = signed_spend.dbc_id();
+
         // Add dbc_id:tx_hash to dbc_id index.
+        let is_new_spend = !self.dbc_ids.contains_key(input_id);
         let existing_tx_hash = self.dbc_ids.entry(*input_id).or_insert_with(|| tx_hash);
 
         if *existing_tx_hash == tx_hash {
+            if is_new_spend {
+                self.append_to_log(spent_tx, signed_spend);
+            }
+
             // Add tx_hash:tx to transaction entries. (primary data store)
             let existing_tx = self
                 .transactions
@@ -175,4 +566,440 @@ impl SpentbookNode {
             Err(crate::mock::Error::DbcAlreadySpent.into())
         }
     }
+
+    // Append a spend to the log, chained to the current last entry.
+    fn append_to_log(&mut self, spent_tx: &DbcTransaction, signed_spend: &SignedSpend) {
+        let mut entry = SpentbookLogEntry {
+            seq: self.log.len() as u64,
+            prev_hash: self.log_head(),
+            spent_tx: spent_tx.clone(),
+            signed_spend: signed_spend.clone(),
+            node_sig: None,
+        };
+        entry.node_sig = self.key.as_ref().map(|k| k.sign(entry.hash()));
+        self.log_index_by_dbc_id
+            .insert(*signed_spend.dbc_id(), self.log.len());
+        self.log.push(entry);
+    }
+}
+
+// A tx which has been validated but not yet committed, with the SignedSpends of its inputs.
+struct StagedTx {
+    hash: Hash,
+    tx: DbcTransaction,
+    signed_spends: BTreeSet<SignedSpend>,
+}
+
+/// Check that a tx is of the network of a spentbook.
+pub(super) fn check_network(network: NetworkId, spent_tx: &DbcTransaction) -> Result<()> {
+    if spent_tx.network != network {
+        return Err(Error::NetworkMismatch {
+            expected: network,
+            found: spent_tx.network,
+        });
+    }
+    Ok(())
+}
+
+impl TransactionSource for SpentbookNode {
+    fn signed_spends(&self, spent_tx_hash: &Hash) -> Option<BTreeSet<SignedSpend>> {
+        self.transactions
+            .get(spent_tx_hash)?
+            .inputs
+            .iter()
+            .map(|input| self.signed_spend(&input.dbc_id()).cloned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock, Dbc, DbcBuilder, InputHistory, MainKey, RevealedAmount, RevealedInput, Token,
+        TransactionBuilder, TransactionVerifier,
+    };
+
+    // Splits the genesis dbc in two, and returns the builder
+    // of a tx merging them back into one.
+    fn merge_builder(
+        spentbook_node: &mut SpentbookNode,
+        genesis_dbc: &Dbc,
+        genesis_main_key: &MainKey,
+        rng: &mut crate::rand::rngs::StdRng,
+    ) -> Result<(DbcBuilder, Vec<Dbc>)> {
+        let main_key = MainKey::random_from_rng(rng);
+        let half = mock::GenesisMaterial::GENESIS_AMOUNT / 2;
+        let derived_key = genesis_dbc.derived_key(genesis_main_key)?;
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(genesis_dbc, &derived_key)?
+            .add_outputs([
+                (Token::from_nano(half), main_key.random_dbc_id_src(rng)),
+                (
+                    Token::from_nano(mock::GenesisMaterial::GENESIS_AMOUNT - half),
+                    main_key.random_dbc_id_src(rng),
+                ),
+            ])
+            .build(Hash::default(), &mut *rng)?;
+        spentbook_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
+
+        let mut tx_builder = TransactionBuilder::default();
+        let mut dbcs = vec![];
+        for (dbc, _) in dbc_builder.build()? {
+            tx_builder = tx_builder.add_input_dbc(&dbc, &dbc.derived_key(&main_key)?)?;
+            dbcs.push(dbc);
+        }
+        let merge_builder = tx_builder
+            .add_output(
+                Token::from_nano(mock::GenesisMaterial::GENESIS_AMOUNT),
+                main_key.random_dbc_id_src(rng),
+            )
+            .build(Hash::default(), rng)?;
+        Ok((merge_builder, dbcs))
+    }
+
+    #[test]
+    fn log_tx_accepts_an_aggregated_signature() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (mut spentbook_node, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis_single(&mut rng)?;
+        let (merge_builder, dbcs) = merge_builder(
+            &mut spentbook_node,
+            &genesis_dbc,
+            &genesis_material.main_key,
+            &mut rng,
+        )?;
+
+        // Rebuild the merge, with the input signatures aggregated.
+        let main_key = MainKey::random_from_rng(&mut rng);
+        let mut tx_builder = TransactionBuilder::default().aggregate_signatures();
+        for (dbc, input) in dbcs.iter().zip(merge_builder.revealed_tx.inputs.iter()) {
+            tx_builder = tx_builder.add_input_dbc(dbc, &input.input.derived_key)?;
+        }
+        let dbc_builder = tx_builder
+            .add_output(
+                Token::from_nano(mock::GenesisMaterial::GENESIS_AMOUNT),
+                main_key.random_dbc_id_src(&mut rng),
+            )
+            .build(Hash::default(), &mut rng)?;
+        assert!(dbc_builder.spent_tx.aggregated_signature.is_some());
+
+        spentbook_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
+        for (dbc, _) in dbc_builder.build()? {
+            dbc.verify(&main_key)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn log_tx_logs_every_input() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (mut spentbook_node, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis_single(&mut rng)?;
+        let (merge_builder, dbcs) = merge_builder(
+            &mut spentbook_node,
+            &genesis_dbc,
+            &genesis_material.main_key,
+            &mut rng,
+        )?;
+
+        spentbook_node.log_tx(&merge_builder.spent_tx, &merge_builder.signed_spends)?;
+        assert!(dbcs.iter().all(|dbc| spentbook_node.is_spent(&dbc.id())));
+
+        // Logging the same tx again changes nothing.
+        let log_len = spentbook_node.log.len();
+        spentbook_node.log_tx(&merge_builder.spent_tx, &merge_builder.signed_spends)?;
+        assert_eq!(spentbook_node.log.len(), log_len);
+
+        Ok(())
+    }
+
+    #[test]
+    fn log_tx_logs_nothing_if_an_input_is_rejected() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (mut spentbook_node, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis_single(&mut rng)?;
+        let (merge_builder, _) = merge_builder(
+            &mut spentbook_node,
+            &genesis_dbc,
+            &genesis_material.main_key,
+            &mut rng,
+        )?;
+        let spent_tx = &merge_builder.spent_tx;
+        let log_len = spentbook_node.log.len();
+
+        // An invalid signature on the last input blocks the first from being logged.
+        let mut signed_spends = merge_builder.signed_spends.clone();
+        let mut invalid_spend = signed_spends.pop_last().unwrap();
+        invalid_spend.derived_key_sig = blsttc::SecretKey::random().sign([0u8; 32]);
+        let invalid_id = *invalid_spend.dbc_id();
+        signed_spends.insert(invalid_spend);
+
+        assert_eq!(
+            spentbook_node.log_tx(spent_tx, &signed_spends),
+            Err(Error::SpendRejected {
+                dbc_id: invalid_id,
+                error: Box::new(Error::InvalidSpendSignature(invalid_id)),
+            })
+        );
+        assert_eq!(spentbook_node.log.len(), log_len);
+        assert!(spent_tx
+            .inputs
+            .iter()
+            .all(|i| !spentbook_node.is_spent(&i.dbc_id())));
+
+        // An input already spent elsewhere blocks the others too.
+        let signed_spend = merge_builder.signed_spends.first().unwrap();
+        let other_tx = DbcTransaction {
+            version: DbcTransaction::VERSION,
+            inputs: spent_tx.inputs.clone(),
+            outputs: vec![],
+            aggregated_signature: None,
+            network: spent_tx.network,
+        };
+        spentbook_node.commit_spend(other_tx.hash(), &other_tx, signed_spend)?;
+
+        assert_eq!(
+            spentbook_node.log_tx(spent_tx, &merge_builder.signed_spends),
+            Err(Error::SpendRejected {
+                dbc_id: *signed_spend.dbc_id(),
+                error: Box::new(mock::Error::DbcAlreadySpent.into()),
+            })
+        );
+        let other_id = spent_tx
+            .inputs
+            .iter()
+            .map(|i| i.dbc_id())
+            .find(|id| id != signed_spend.dbc_id())
+            .unwrap();
+        assert!(!spentbook_node.is_spent(&other_id));
+
+        Ok(())
+    }
+
+    #[test]
+    fn a_fresh_node_learns_the_parent_tx_from_the_spend() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, genesis_dbc, dbc, main_key) =
+            crate::dbc::tests::generate_dbc_and_its_main_key(100, &mut rng)?;
+
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(&dbc, &dbc.derived_key(&main_key)?)?
+            .add_output(Token::from_nano(100), main_key.random_dbc_id_src(&mut rng))
+            .build(Hash::default(), &mut rng)?;
+        let spent_tx = &dbc_builder.spent_tx;
+
+        // A node that has not seen the tx creating the dbc can not log its spend.
+        let mut spentbook_node = SpentbookNode::default();
+        assert_eq!(
+            spentbook_node.log_tx(spent_tx, &dbc_builder.signed_spends),
+            Err(Error::SpendRejected {
+                dbc_id: dbc.id(),
+                error: Box::new(Error::MissingAmountForDbcId(dbc.id())),
+            })
+        );
+
+        // Nor can it learn that tx without the spends that produced it.
+        assert!(spentbook_node
+            .log_tx_with_parents(spent_tx, &dbc_builder.signed_spends, &BTreeSet::new())
+            .is_err());
+
+        // Nor from the spends of its parent tx only, as the inputs of the parent tx
+        // were created by the genesis tx, which is unknown too.
+        assert!(spentbook_node
+            .log_tx_with_parents(spent_tx, &dbc_builder.signed_spends, &dbc.signed_spends)
+            .is_err());
+
+        // The spends of its parent tx are carried by the dbc itself,
+        // and those of the genesis tx by the genesis dbc.
+        let lineage_spends: BTreeSet<SignedSpend> = dbc
+            .signed_spends
+            .union(&genesis_dbc.signed_spends)
+            .cloned()
+            .collect();
+        spentbook_node.log_tx_with_parents(
+            spent_tx,
+            &dbc_builder.signed_spends,
+            &lineage_spends,
+        )?;
+        assert!(spentbook_node.is_spent(&dbc.id()));
+        // The inputs of the learned txs are logged as spent too.
+        assert!(spentbook_node.is_spent(&genesis_dbc.id()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parent_txs_are_only_learned_along_with_the_spend() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, genesis_dbc, dbc, main_key) =
+            crate::dbc::tests::generate_dbc_and_its_main_key(100, &mut rng)?;
+
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(&dbc, &dbc.derived_key(&main_key)?)?
+            .add_output(Token::from_nano(100), main_key.random_dbc_id_src(&mut rng))
+            .build(Hash::default(), &mut rng)?;
+        let spent_tx = &dbc_builder.spent_tx;
+        let signed_spend = dbc_builder.signed_spends.first().unwrap();
+        let lineage_spends: BTreeSet<SignedSpend> = dbc
+            .signed_spends
+            .union(&genesis_dbc.signed_spends)
+            .cloned()
+            .collect();
+        let mut spentbook_node = SpentbookNode::default();
+
+        // The parent txs are valid, but the tx spending the dbc is not,
+        // so none of them are logged.
+        let mut invalid_tx = spent_tx.clone();
+        invalid_tx.outputs.clear();
+        assert!(spentbook_node
+            .log_tx_with_parents(&invalid_tx, &dbc_builder.signed_spends, &lineage_spends)
+            .is_err());
+        assert!(spentbook_node
+            .log_spent_with_parents(&invalid_tx, signed_spend, &lineage_spends)
+            .is_err());
+        assert!(spentbook_node.export_log().is_empty());
+
+        // The dbc is two txs away from the genesis input.
+        assert_eq!(
+            spentbook_node.log_tx_with_ancestry(
+                spent_tx,
+                &dbc_builder.signed_spends,
+                &lineage_spends,
+                1
+            ),
+            Err(Error::SpendRejected {
+                dbc_id: dbc.id(),
+                error: Box::new(mock::Error::AncestryTooDeep.into()),
+            })
+        );
+        assert!(spentbook_node.export_log().is_empty());
+
+        spentbook_node.log_tx_with_ancestry(
+            spent_tx,
+            &dbc_builder.signed_spends,
+            &lineage_spends,
+            2,
+        )?;
+        assert!(spentbook_node.is_spent(&dbc.id()));
+        assert!(spentbook_node.is_spent(&genesis_dbc.id()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn a_forged_parent_tx_is_not_learned() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+
+        // A parent tx minting tokens out of a made-up input, which is consistent on its own.
+        let forged_key = MainKey::random_from_rng(&mut rng).random_derived_key(&mut rng);
+        let forged_input = InputHistory {
+            input: RevealedInput::new(
+                forged_key.clone(),
+                RevealedAmount::from_amount(1_000_000, &mut rng),
+            ),
+            input_src_tx: DbcTransaction {
+                version: DbcTransaction::VERSION,
+                inputs: vec![],
+                outputs: vec![],
+                aggregated_signature: None,
+                network: NetworkId::Mainnet,
+            },
+        };
+        let main_key = MainKey::random_from_rng(&mut rng);
+        let forged_builder = TransactionBuilder::default()
+            .add_input(forged_input)
+            .add_output(
+                Token::from_nano(1_000_000),
+                main_key.random_dbc_id_src(&mut rng),
+            )
+            .build(Hash::default(), &mut rng)?;
+        TransactionVerifier::verify(&forged_builder.spent_tx, &forged_builder.signed_spends)?;
+        let forged_spends = forged_builder.signed_spends.clone();
+        let (dbc, _) = forged_builder.build()?.into_iter().next().unwrap();
+
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(&dbc, &dbc.derived_key(&main_key)?)?
+            .add_output(
+                Token::from_nano(1_000_000),
+                main_key.random_dbc_id_src(&mut rng),
+            )
+            .build(Hash::default(), &mut rng)?;
+
+        // The made-up input is neither genesis nor an output known to the node.
+        let mut spentbook_node = SpentbookNode::default();
+        assert!(spentbook_node
+            .log_tx_with_parents(
+                &dbc_builder.spent_tx,
+                &dbc_builder.signed_spends,
+                &forged_spends,
+            )
+            .is_err());
+        assert!(!spentbook_node.is_spent(&dbc.id()));
+        assert!(!spentbook_node.is_spent(&forged_key.dbc_id()));
+        assert!(spentbook_node.export_log().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn txs_of_another_network_are_rejected() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (testnet_nodes, genesis_dbc, genesis_material, _) = mock::GenesisBuilder::default()
+            .network(NetworkId::Testnet)
+            .gen_spentbook_nodes(1)
+            .build(&mut rng)?;
+        let mut testnet_node = testnet_nodes.into_iter().next().unwrap();
+        assert_eq!(genesis_dbc.src_tx.network, NetworkId::Testnet);
+
+        let derived_key = genesis_dbc.derived_key(&genesis_material.main_key)?;
+        let output = (
+            Token::from_nano(mock::GenesisMaterial::GENESIS_AMOUNT),
+            genesis_material.main_key.random_dbc_id_src(&mut rng),
+        );
+
+        // A testnet dbc can not be spent in a mainnet tx.
+        let mainnet_builder = TransactionBuilder::default()
+            .add_input_dbc(&genesis_dbc, &derived_key)?
+            .add_outputs([output]);
+        assert_eq!(
+            mainnet_builder.build(Hash::default(), &mut rng).map(|_| ()),
+            Err(Error::NetworkMismatch {
+                expected: NetworkId::Mainnet,
+                found: NetworkId::Testnet,
+            })
+        );
+
+        // Nor is a testnet tx logged by a mainnet node.
+        let dbc_builder = TransactionBuilder::default()
+            .network(NetworkId::Testnet)
+            .add_input_dbc(&genesis_dbc, &derived_key)?
+            .add_outputs([output])
+            .build(Hash::default(), &mut rng)?;
+        let mut mainnet_node = SpentbookNode::default();
+        assert_eq!(
+            mainnet_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends),
+            Err(Error::NetworkMismatch {
+                expected: NetworkId::Mainnet,
+                found: NetworkId::Testnet,
+            })
+        );
+        let signed_spend = dbc_builder.signed_spends.first().unwrap();
+        assert_eq!(
+            mainnet_node.log_spent(&dbc_builder.spent_tx, signed_spend),
+            Err(Error::NetworkMismatch {
+                expected: NetworkId::Mainnet,
+                found: NetworkId::Testnet,
+            })
+        );
+
+        testnet_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
+
+        // A testnet node is rebuilt from its log as a testnet node.
+        let replayed = SpentbookNode::from_log(&testnet_node.export_log(), None)?;
+        assert_eq!(replayed.network, NetworkId::Testnet);
+        assert_eq!(replayed.log_head(), testnet_node.log_head());
+        Ok(())
+    }
 }
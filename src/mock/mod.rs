@@ -6,12 +6,16 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod concurrent_spentbook;
 mod error;
 mod genesis_builder;
 mod genesis_material;
 mod mock_spentbook;
+pub mod spentbook_log;
+pub mod spentbook_sync;
 
 pub use self::{
-    error::Error, genesis_builder::GenesisBuilder, genesis_material::GenesisMaterial,
-    mock_spentbook::SpentbookNode,
+    concurrent_spentbook::ConcurrentSpentbook, error::Error, genesis_builder::GenesisBuilder,
+    genesis_material::GenesisMaterial, mock_spentbook::SpentbookNode,
+    spentbook_log::SpentbookLogEntry,
 };
@@ -0,0 +1,194 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Error;
+use crate::{dbc_id::PublicAddress, DbcTransaction, Hash, Result, Signature, SignedSpend};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A single entry of the append-only spentbook log.
+///
+/// Every spend that a SpentbookNode accepts is appended to its log as an entry.
+/// Each entry carries its position in the log, and the hash of the entry before it,
+/// so that the log forms a hash chain. Any modification, removal or reordering
+/// of past entries will thus break the chain from that point on.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpentbookLogEntry {
+    /// The position of this entry in the log, starting at 0.
+    pub seq: u64,
+    /// The hash of the previous entry. The default Hash for the first entry.
+    pub prev_hash: Hash,
+    /// The transaction the input Dbc was spent in.
+    pub spent_tx: DbcTransaction,
+    /// The spend that was logged.
+    pub signed_spend: SignedSpend,
+    /// The signature of the node over the hash of this entry, if the node has a key.
+    pub node_sig: Option<Signature>,
+}
+
+impl SpentbookLogEntry {
+    /// Represent this entry as bytes.
+    /// The node signature is not included, as it is made over these bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Default::default();
+        bytes.extend(self.seq.to_le_bytes());
+        bytes.extend(self.prev_hash.as_ref());
+        bytes.extend(self.spent_tx.hash().as_ref());
+        bytes.extend(self.signed_spend.to_bytes());
+        bytes
+    }
+
+    /// Represent this entry as a Hash.
+    pub fn hash(&self) -> Hash {
+        Hash::hash(&self.to_bytes())
+    }
+}
+
+/// Verifies the hash chain of a spentbook log, from the first entry to the last.
+///
+/// If a signer is provided, every entry must also carry a valid signature
+/// of that signer over the entry hash. Otherwise signatures are not checked.
+///
+/// Returns the hash of the last entry, i.e. the head of the log.
+pub fn verify_log(log: &[SpentbookLogEntry], signer: Option<&PublicAddress>) -> Result<Hash> {
+    let mut prev_hash = Hash::default();
+    for (expected, entry) in (0u64..).zip(log) {
+        if entry.seq != expected {
+            return Err(Error::LogEntryOutOfSequence {
+                expected,
+                found: entry.seq,
+            }
+            .into());
+        }
+        if entry.prev_hash != prev_hash {
+            return Err(Error::LogEntryPrevHashMismatch(entry.seq).into());
+        }
+        if entry.signed_spend.spent_tx_hash() != entry.spent_tx.hash() {
+            return Err(crate::Error::InvalidTransactionHash);
+        }
+
+        let entry_hash = entry.hash();
+        if let Some(signer) = signer {
+            match &entry.node_sig {
+                Some(sig) if signer.verify(sig, entry_hash.as_ref()) => {}
+                _ => return Err(Error::LogEntryInvalidSignature(entry.seq).into()),
+            }
+        }
+        prev_hash = entry_hash;
+    }
+    Ok(prev_hash)
+}
+
+/// Returns the sequence number of the first entry where the two logs differ,
+/// or None if one log is a prefix of the other.
+///
+/// Two replicas which have logged the same spends in the same order
+/// will have identical logs. A differing entry means the replicas have forked.
+pub fn find_fork(a: &[SpentbookLogEntry], b: &[SpentbookLogEntry]) -> Option<u64> {
+    a.iter()
+        .zip(b)
+        .find(|(x, y)| x.hash() != y.hash())
+        .map(|(x, _)| x.seq)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, Error};
+
+    #[test]
+    fn replaying_a_log_rebuilds_the_spentbook() -> Result<(), Error> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (spentbook_node, ..) = crate::dbc::tests::generate_dbc_and_its_main_key(100, &mut rng)?;
+
+        let log = spentbook_node.export_log();
+        assert_eq!(log.len(), 2);
+        let head = verify_log(&log, None)?;
+        assert_eq!(head, spentbook_node.log_head());
+
+        let replayed = mock::SpentbookNode::from_log(&log, None)?;
+        assert_eq!(replayed.log_head(), head);
+        assert_eq!(replayed.dbc_ids, spentbook_node.dbc_ids);
+        assert_eq!(find_fork(&log, &replayed.export_log()), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn replaying_a_signed_log_keeps_its_signatures() -> Result<(), Error> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let node_key = blsttc::SecretKey::random();
+        let node_id = PublicAddress::new(node_key.public_key());
+        let (mut spentbook_nodes, ..) = mock::GenesisBuilder::default()
+            .add_spentbook_node(mock::SpentbookNode::with_key(node_key))
+            .build(&mut rng)?;
+        let log = spentbook_nodes.remove(0).export_log();
+
+        let replayed = mock::SpentbookNode::from_log(&log, Some(&node_id))?;
+        assert_eq!(replayed.id, node_id);
+        assert_eq!(replayed.export_log(), log);
+        assert!(verify_log(&replayed.export_log(), Some(&node_id)).is_ok());
+
+        // An entry that does not log a new spend is not reproduced.
+        let mut repeated = log.clone();
+        let mut entry = log[0].clone();
+        entry.seq = 1;
+        entry.prev_hash = log[0].hash();
+        repeated.push(entry);
+        assert_eq!(
+            mock::SpentbookNode::from_log(&repeated, None).map(|_| ()),
+            Err(mock::Error::LogEntryNotReplayed(1).into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_log_fails_verification() -> Result<(), Error> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let node_key = blsttc::SecretKey::random();
+        let node_id = PublicAddress::new(node_key.public_key());
+        let (mut spentbook_nodes, genesis_dbc, ..) = mock::GenesisBuilder::default()
+            .add_spentbook_node(mock::SpentbookNode::with_key(node_key))
+            .build(&mut rng)?;
+        let spentbook_node = spentbook_nodes.remove(0);
+
+        let log = spentbook_node.export_log();
+        assert!(verify_log(&log, Some(&node_id)).is_ok());
+
+        // Signatures by another key are not accepted.
+        let other_id = PublicAddress::new(blsttc::SecretKey::random().public_key());
+        assert_eq!(
+            verify_log(&log, Some(&other_id)),
+            Err(mock::Error::LogEntryInvalidSignature(0).into())
+        );
+
+        // Rewriting history breaks the chain.
+        let mut forked = log.clone();
+        forked[0].prev_hash = genesis_dbc.hash();
+        assert_eq!(
+            verify_log(&forked, None),
+            Err(mock::Error::LogEntryPrevHashMismatch(0).into())
+        );
+        assert_eq!(find_fork(&log, &forked), Some(0));
+
+        forked[0].seq = 1;
+        assert_eq!(
+            verify_log(&forked, None),
+            Err(mock::Error::LogEntryOutOfSequence {
+                expected: 0,
+                found: 1
+            }
+            .into())
+        );
+
+        Ok(())
+    }
+}
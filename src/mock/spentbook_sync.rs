@@ -0,0 +1,271 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Anti-entropy sync between spentbook replicas.
+//!
+//! A round of sync between two nodes goes like this:
+//!
+//! 1. Each node produces a `SyncSummary` of the spends it knows of.
+//! 2. On receiving the summary of the other node, a node produces a `SyncRequest`
+//!    for every spend it is missing, or that conflicts with its own.
+//! 3. The node receiving the request answers with a `SyncResponse`, holding the
+//!    requested SignedSpends, along with the transactions they were spent in.
+//! 4. The requesting node imports the response. Each spend is verified again before
+//!    it is logged, and a spend that conflicts with an already logged one is returned
+//!    as `DoubleSpendEvidence`, instead of being dropped.
+//!
+//! The message types are plain data, so that they could be sent over a network.
+//! `sync_nodes` drives a full round between two local nodes.
+
+use super::SpentbookNode;
+use crate::{DbcId, DbcTransaction, Error, Hash, Result, SignedSpend};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The spends known to a node, as DbcId --> hash of the Spend.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub spends: BTreeMap<DbcId, Hash>,
+}
+
+/// The DbcIds for which a node wants the spends of another node.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncRequest {
+    pub wanted: BTreeSet<DbcId>,
+}
+
+/// The requested spends, along with the transactions they were spent in.
+/// They are in the order they were logged by the responding node, so that
+/// the tx creating an input is always imported before the tx spending it.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct SyncResponse {
+    pub spends: Vec<(DbcTransaction, SignedSpend)>,
+}
+
+/// Two different spends of the same Dbc, both signed by its DerivedKey.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoubleSpendEvidence {
+    pub dbc_id: DbcId,
+    /// The spend that was already logged by the node.
+    pub ours: SignedSpend,
+    /// The conflicting spend received from the other node.
+    pub theirs: SignedSpend,
+}
+
+impl DoubleSpendEvidence {
+    /// Verifies that both spends are of the same Dbc, that they differ,
+    /// and that both are validly signed by the DerivedKey of the Dbc.
+    pub fn verify(&self) -> Result<()> {
+        if self.ours.dbc_id() != &self.dbc_id || self.theirs.dbc_id() != &self.dbc_id {
+            return Err(Error::SignedSpendInputIdMismatch);
+        }
+        if self.ours.spend.hash() == self.theirs.spend.hash() {
+            return Err(super::Error::NotADoubleSpend(self.dbc_id).into());
+        }
+        self.ours.verify(self.ours.spent_tx_hash())?;
+        self.theirs.verify(self.theirs.spent_tx_hash())
+    }
+}
+
+/// The result of importing a SyncResponse.
+#[derive(Debug, Clone, Default)]
+pub struct SyncOutcome {
+    /// The spends that were logged.
+    pub imported: Vec<DbcId>,
+    /// The spends that conflict with those already logged.
+    pub double_spends: Vec<DoubleSpendEvidence>,
+    /// The spends that failed verification, and were not logged.
+    pub rejected: Vec<(DbcId, Error)>,
+}
+
+impl SpentbookNode {
+    /// Summarise the spends known to this node.
+    pub fn sync_summary(&self) -> SyncSummary {
+        SyncSummary {
+            spends: self
+                .log
+                .iter()
+                .map(|e| (*e.signed_spend.dbc_id(), e.signed_spend.spend.hash()))
+                .collect(),
+        }
+    }
+
+    /// Request every spend of the other node which is missing here,
+    /// or which differs from the spend logged here.
+    pub fn sync_request(&self, theirs: &SyncSummary) -> SyncRequest {
+        SyncRequest {
+            wanted: theirs
+                .spends
+                .iter()
+                .filter(|(dbc_id, spend_hash)| {
+                    self.signed_spend(dbc_id)
+                        .map(|s| s.spend.hash() != **spend_hash)
+                        .unwrap_or(true)
+                })
+                .map(|(dbc_id, _)| *dbc_id)
+                .collect(),
+        }
+    }
+
+    /// Answer a request with the wanted spends, in the order they were logged.
+    /// Spends unknown to this node are left out.
+    pub fn sync_response(&self, request: &SyncRequest) -> SyncResponse {
+        SyncResponse {
+            spends: self
+                .log
+                .iter()
+                .filter(|e| request.wanted.contains(e.signed_spend.dbc_id()))
+                .map(|e| (e.spent_tx.clone(), e.signed_spend.clone()))
+                .collect(),
+        }
+    }
+
+    /// Import the spends of a response.
+    ///
+    /// Every spend is verified, along with the tx it was spent in, before it is logged.
+    /// A spend conflicting with one already logged is not imported, but returned as evidence.
+    pub fn import_sync_response(&mut self, response: SyncResponse) -> SyncOutcome {
+        let mut outcome = SyncOutcome::default();
+
+        for (spent_tx, signed_spend) in response.spends {
+            let dbc_id = *signed_spend.dbc_id();
+
+            if let Err(err) = signed_spend.verify(spent_tx.hash()) {
+                outcome.rejected.push((dbc_id, err));
+                continue;
+            }
+
+            if let Some(ours) = self.signed_spend(&dbc_id) {
+                if ours.spend.hash() != signed_spend.spend.hash() {
+                    outcome.double_spends.push(DoubleSpendEvidence {
+                        dbc_id,
+                        ours: ours.clone(),
+                        theirs: signed_spend,
+                    });
+                }
+                continue;
+            }
+
+            match self.log_spent(&spent_tx, &signed_spend) {
+                Ok(()) => outcome.imported.push(dbc_id),
+                Err(err) => outcome.rejected.push((dbc_id, err)),
+            }
+        }
+
+        outcome
+    }
+}
+
+/// Run a full round of sync between two local nodes.
+///
+/// Returns the outcome of the import on `a`, and the outcome of the import on `b`.
+pub fn sync_nodes(a: &mut SpentbookNode, b: &mut SpentbookNode) -> (SyncOutcome, SyncOutcome) {
+    let a_summary = a.sync_summary();
+    let b_summary = b.sync_summary();
+
+    let a_request = a.sync_request(&b_summary);
+    let b_request = b.sync_request(&a_summary);
+
+    let a_response = a.sync_response(&b_request);
+    let b_response = b.sync_response(&a_request);
+
+    let a_outcome = a.import_sync_response(b_response);
+    let b_outcome = b.import_sync_response(a_response);
+
+    (a_outcome, b_outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, MainKey, Token, TransactionBuilder};
+
+    #[test]
+    fn sync_transfers_missing_spends() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (mut spentbook_nodes, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis(2, &mut rng)?;
+
+        let main_key = MainKey::random_from_rng(&mut rng);
+        let derived_key = genesis_dbc.derived_key(&genesis_material.main_key)?;
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(&genesis_dbc, &derived_key)?
+            .add_output(
+                Token::from_nano(mock::GenesisMaterial::GENESIS_AMOUNT),
+                main_key.random_dbc_id_src(&mut rng),
+            )
+            .build(Hash::default(), &mut rng)?;
+
+        // Only the first node learns of the spend.
+        let tx = &dbc_builder.spent_tx;
+        for signed_spend in dbc_builder.signed_spends() {
+            spentbook_nodes[0].log_spent(tx, signed_spend)?;
+        }
+        assert!(!spentbook_nodes[1].is_spent(&genesis_dbc.id()));
+
+        let (mut a, mut b) = (spentbook_nodes.remove(0), spentbook_nodes.remove(0));
+        let (a_outcome, b_outcome) = sync_nodes(&mut a, &mut b);
+
+        assert!(a_outcome.imported.is_empty());
+        assert_eq!(b_outcome.imported, vec![genesis_dbc.id()]);
+        assert!(b_outcome.double_spends.is_empty() && b_outcome.rejected.is_empty());
+        assert!(b.is_spent(&genesis_dbc.id()));
+        assert_eq!(a.log_head(), b.log_head());
+
+        // A second round has nothing to transfer.
+        let (a_outcome, b_outcome) = sync_nodes(&mut a, &mut b);
+        assert!(a_outcome.imported.is_empty() && b_outcome.imported.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sync_surfaces_double_spends() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (mut spentbook_nodes, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis(2, &mut rng)?;
+        let derived_key = genesis_dbc.derived_key(&genesis_material.main_key)?;
+
+        // Spend the genesis dbc twice, logging each spend to a different node.
+        for spentbook_node in spentbook_nodes.iter_mut() {
+            let main_key = MainKey::random_from_rng(&mut rng);
+            let dbc_builder = TransactionBuilder::default()
+                .add_input_dbc(&genesis_dbc, &derived_key)?
+                .add_output(
+                    Token::from_nano(mock::GenesisMaterial::GENESIS_AMOUNT),
+                    main_key.random_dbc_id_src(&mut rng),
+                )
+                .build(Hash::default(), &mut rng)?;
+            for signed_spend in dbc_builder.signed_spends() {
+                spentbook_node.log_spent(&dbc_builder.spent_tx, signed_spend)?;
+            }
+        }
+
+        let (mut a, mut b) = (spentbook_nodes.remove(0), spentbook_nodes.remove(0));
+        let a_spend = a.signed_spend(&genesis_dbc.id()).cloned();
+        let (a_outcome, b_outcome) = sync_nodes(&mut a, &mut b);
+
+        for outcome in [&a_outcome, &b_outcome] {
+            assert!(outcome.imported.is_empty());
+            assert_eq!(outcome.double_spends.len(), 1);
+            assert_eq!(outcome.double_spends[0].dbc_id, genesis_dbc.id());
+            assert!(outcome.double_spends[0].verify().is_ok());
+        }
+        // The spend logged first is kept.
+        assert_eq!(a.signed_spend(&genesis_dbc.id()).cloned(), a_spend);
+        assert_eq!(b_outcome.double_spends[0].theirs, a_spend.unwrap());
+
+        Ok(())
+    }
+}
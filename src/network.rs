@@ -0,0 +1,57 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+/// The network that addresses, ids and txs are meant for.
+///
+/// A tx commits to its network: it is part of the signed tx message, of the range
+/// proof transcripts and of each Spend, so that a tx of one network is not valid on another.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum NetworkId {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl NetworkId {
+    /// The name of the network, which is also what a tx commits to.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Mainnet => "mainnet",
+            Self::Testnet => "testnet",
+        }
+    }
+
+    /// The prefix of a `PublicAddress` string on this network.
+    pub const fn address_prefix(self) -> &'static str {
+        match self {
+            Self::Mainnet => "safe",
+            Self::Testnet => "tsafe",
+        }
+    }
+
+    /// The prefix of a `DbcId` string on this network.
+    pub const fn dbc_id_prefix(self) -> &'static str {
+        match self {
+            Self::Mainnet => "safeid",
+            Self::Testnet => "tsafeid",
+        }
+    }
+}
+
+impl fmt::Display for NetworkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Helpers spreading independent work across cores with the `parallel` feature,
+//! and running it sequentially without it.
+//!
+//! Results are always in the order of the items, so that both paths
+//! produce the same output, and fail with the same error.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Map every item with `f`, keeping the order of the items.
+pub(crate) fn map<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        items.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().map(f).collect()
+    }
+}
+
+/// Check every item with `f`, returning the error of the first failing item in order.
+pub(crate) fn try_for_each<T, E, F>(items: &[T], f: F) -> Result<(), E>
+where
+    T: Sync,
+    E: Send,
+    F: Fn(&T) -> Result<(), E> + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        match items.par_iter().find_map_first(|item| f(item).err()) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().try_for_each(f)
+    }
+}
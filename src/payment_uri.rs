@@ -0,0 +1,322 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    rand::RngCore, AddressParseError, DbcIdSource, Error, Hash, NetworkId, PublicAddress, Result,
+    Token, TokenFormat, TokenParseError,
+};
+
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+const SCHEME: &str = "safe:";
+
+/// An error of parsing a `PaymentUri`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PaymentUriError {
+    #[error("The URI does not start with {SCHEME:?}.")]
+    MissingScheme,
+    #[error("Invalid address: {0}")]
+    Address(#[from] AddressParseError),
+    #[error("Invalid amount: {0}")]
+    Amount(#[from] TokenParseError),
+    #[error("Invalid reason, it is not the hex of a hash.")]
+    InvalidReason,
+    #[error("Parameter {0:?} has no value.")]
+    MissingValue(String),
+    #[error("Unknown parameter {0:?}.")]
+    UnknownParameter(String),
+    #[error("Parameter {0:?} is given more than once.")]
+    DuplicateParameter(String),
+    #[error("Invalid percent-encoding in {0:?}.")]
+    InvalidEncoding(String),
+    #[error("The URI requests no amount.")]
+    MissingAmount,
+}
+
+/// A request to pay to a `PublicAddress`, for links and QR codes.
+///
+/// ```text
+/// safe:<address>?amount=1.5&reason=<hash>&label=Coffee%20shop
+/// ```
+///
+/// The address is in the form of `PublicAddress::to_bech32()`. All parameters are
+/// optional: `amount` is in tokens, as formatted by the default `TokenFormat`, `reason`
+/// is the hex of a `Hash`, and `label` is percent-encoded text. Unknown or repeated
+/// parameters are rejected, so that a wallet never silently ignores part of a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentUri {
+    pub address: PublicAddress,
+    pub amount: Option<Token>,
+    pub reason: Option<Hash>,
+    pub label: Option<String>,
+}
+
+impl PaymentUri {
+    /// A request to pay to the address, with no parameters.
+    pub fn new(address: PublicAddress) -> Self {
+        Self {
+            address,
+            amount: None,
+            reason: None,
+            label: None,
+        }
+    }
+
+    /// Request the amount.
+    pub fn amount(mut self, amount: Token) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    /// Request that the payment is made for the reason.
+    pub fn reason(mut self, reason: Hash) -> Self {
+        self.reason = Some(reason);
+        self
+    }
+
+    /// Name the recipient, or the purpose of the payment, for display in wallets.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// The URI for the network, with the address in the form of that network.
+    pub fn to_uri(&self, network: NetworkId) -> String {
+        let mut uri = format!("{SCHEME}{}", self.address.to_bech32(network));
+        let mut params = vec![];
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", TokenFormat::default().format(amount)));
+        }
+        if let Some(reason) = self.reason {
+            params.push(format!("reason={}", reason.to_hex()));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        if !params.is_empty() {
+            uri.push('?');
+            uri.push_str(&params.join("&"));
+        }
+        uri
+    }
+
+    /// Parse a URI for the network, see `to_uri()`.
+    pub fn parse(uri: &str, network: NetworkId) -> Result<Self, PaymentUriError> {
+        let rest = uri
+            .get(..SCHEME.len())
+            .filter(|scheme| scheme.eq_ignore_ascii_case(SCHEME))
+            .map(|_| &uri[SCHEME.len()..])
+            .ok_or(PaymentUriError::MissingScheme)?;
+        let (address, query) = match rest.split_once('?') {
+            Some((address, query)) => (address, Some(query)),
+            None => (rest, None),
+        };
+        let mut payment = Self::new(PublicAddress::from_bech32(address, network)?);
+
+        for param in query.into_iter().flat_map(|q| q.split('&')) {
+            let (name, value) = param
+                .split_once('=')
+                .filter(|(_, value)| !value.is_empty())
+                .ok_or_else(|| PaymentUriError::MissingValue(param.to_string()))?;
+            let duplicate = || PaymentUriError::DuplicateParameter(name.to_string());
+            match name {
+                "amount" if payment.amount.is_some() => return Err(duplicate()),
+                "amount" => payment.amount = Some(TokenFormat::default().parse_strict(value)?),
+                "reason" if payment.reason.is_some() => return Err(duplicate()),
+                "reason" => {
+                    payment.reason =
+                        Some(Hash::from_hex(value).map_err(|_| PaymentUriError::InvalidReason)?)
+                }
+                "label" if payment.label.is_some() => return Err(duplicate()),
+                "label" => payment.label = Some(percent_decode(value)?),
+                _ => return Err(PaymentUriError::UnknownParameter(name.to_string())),
+            }
+        }
+        Ok(payment)
+    }
+
+    /// The output paying the requested amount to a new DbcId of the address, for
+    /// `TransactionBuilder::add_output()`. The requested reason, if any, is then
+    /// given to `TransactionBuilder::build()`.
+    pub fn output(&self, rng: &mut impl RngCore) -> Result<(Token, DbcIdSource)> {
+        let amount = self
+            .amount
+            .ok_or(Error::PaymentUri(PaymentUriError::MissingAmount))?;
+        Ok((amount, self.address.random_dbc_id_src(rng)))
+    }
+}
+
+impl fmt::Display for PaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_uri(NetworkId::Mainnet))
+    }
+}
+
+impl FromStr for PaymentUri {
+    type Err = PaymentUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, NetworkId::Mainnet)
+    }
+}
+
+/// Percent-encode all but the unreserved characters of RFC 3986.
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn percent_decode(s: &str) -> Result<String, PaymentUriError> {
+    let invalid = || PaymentUriError::InvalidEncoding(s.to_string());
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((byte, tail)) = rest.split_first() {
+        if *byte == b'%' {
+            let hex = tail.get(..2).ok_or_else(invalid)?;
+            let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(*byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, MainKey, TransactionBuilder};
+
+    #[test]
+    fn payment_uri_round_trips() -> Result<(), PaymentUriError> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let address = MainKey::random_from_rng(&mut rng).public_address();
+
+        let bare = PaymentUri::new(address);
+        assert_eq!(bare.to_string(), format!("safe:{}", address));
+        assert_eq!(bare.to_string().parse::<PaymentUri>()?, bare);
+
+        let payment = PaymentUri::new(address)
+            .amount(Token::from_nano(1_500_000_000))
+            .reason(Hash::hash(b"invoice 42"))
+            .label("Café & co");
+        let uri = payment.to_string();
+        assert!(uri.contains("?amount=1.5&reason="));
+        assert!(uri.ends_with("&label=Caf%C3%A9%20%26%20co"));
+        assert_eq!(uri.parse::<PaymentUri>()?, payment);
+
+        let testnet = payment.to_uri(NetworkId::Testnet);
+        assert!(testnet.starts_with("safe:tsafe1"));
+        assert_eq!(PaymentUri::parse(&testnet, NetworkId::Testnet)?, payment);
+        assert!(matches!(
+            testnet.parse::<PaymentUri>(),
+            Err(PaymentUriError::Address(
+                AddressParseError::WrongPrefix { .. }
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_payment_uris_are_rejected() {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let address = MainKey::random_from_rng(&mut rng).public_address();
+        let uri = |query: &str| format!("safe:{}?{}", address, query);
+
+        for (uri, error) in [
+            (
+                format!("bitcoin:{}", address),
+                PaymentUriError::MissingScheme,
+            ),
+            (
+                uri("amount=1&amount=2"),
+                PaymentUriError::DuplicateParameter("amount".to_string()),
+            ),
+            (
+                uri("label=a&label=b"),
+                PaymentUriError::DuplicateParameter("label".to_string()),
+            ),
+            (
+                uri("amount=1&message=hi"),
+                PaymentUriError::UnknownParameter("message".to_string()),
+            ),
+            (
+                uri("amount"),
+                PaymentUriError::MissingValue("amount".to_string()),
+            ),
+            (uri("reason=abc"), PaymentUriError::InvalidReason),
+            (
+                uri("label=%E9"),
+                PaymentUriError::InvalidEncoding("%E9".to_string()),
+            ),
+            (
+                uri("amount=1,5"),
+                PaymentUriError::Amount(TokenParseError::InvalidCharacter {
+                    character: ',',
+                    index: 1,
+                }),
+            ),
+        ] {
+            assert_eq!(uri.parse::<PaymentUri>(), Err(error), "{:?}", uri);
+        }
+    }
+
+    #[test]
+    fn pay_a_payment_uri() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (mut spentbook_node, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis_single(&mut rng)?;
+        let recipient = MainKey::random_from_rng(&mut rng);
+
+        let uri = PaymentUri::new(recipient.public_address())
+            .amount(Token::from_nano(100))
+            .reason(Hash::hash(b"invoice 42"))
+            .to_string();
+        let payment: PaymentUri = uri.parse()?;
+        assert_eq!(
+            PaymentUri::new(recipient.public_address())
+                .output(&mut rng)
+                .map(|_| ()),
+            Err(Error::PaymentUri(PaymentUriError::MissingAmount))
+        );
+
+        let change = mock::GenesisMaterial::GENESIS_AMOUNT - 100;
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(
+                &genesis_dbc,
+                &genesis_dbc.derived_key(&genesis_material.main_key)?,
+            )?
+            .add_outputs([payment.output(&mut rng)?])
+            .add_output(
+                Token::from_nano(change),
+                genesis_material.main_key.random_dbc_id_src(&mut rng),
+            )
+            .build(payment.reason.unwrap_or_default(), &mut rng)?;
+        spentbook_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
+
+        let (dbc, amount) = dbc_builder
+            .build()?
+            .into_iter()
+            .find(|(dbc, _)| dbc.derived_key(&recipient).is_ok())
+            .ok_or(Error::DbcIdNotFound)?;
+        assert_eq!(amount.value(), 100);
+        assert_eq!(dbc.reason(), Hash::hash(b"invoice 42"));
+        dbc.verify(&recipient)?;
+        Ok(())
+    }
+}
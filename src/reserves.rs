@@ -0,0 +1,324 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    rand::{CryptoRng, RngCore},
+    transaction::{Amount, AmountRangeProof, DbcTransaction, RevealedAmount},
+    BlindedAmount, Dbc, DbcId, DerivedKey, Error, Hash, Result, Signature, SignedSpend,
+    TransactionVerifier, VerifiedTxCache, DEFAULT_VERIFIED_TX_CACHE_CAPACITY,
+};
+
+use std::collections::BTreeSet;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A Dbc held in reserve, as shown in a `ReservesProof`.
+///
+/// The Dbc is shown without its ciphers, so its amount and recipient are not revealed.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ReservesEntry {
+    pub dbc_id: DbcId,
+    /// The tx where the Dbc was created, holding its blinded amount.
+    pub src_tx: DbcTransaction,
+    /// The SignedSpends of the inputs of the src_tx.
+    pub signed_spends: BTreeSet<SignedSpend>,
+    /// The signature of the DerivedKey of the Dbc, proving that it is held.
+    pub derived_key_sig: Signature,
+}
+
+/// A proof of holding Dbcs worth at least `min` in total, without revealing the
+/// amount of any of them, or the total.
+///
+/// The blinded amounts of the Dbcs add up to a blinded amount of their total, which
+/// is proven to be at least `min` by an `AmountRangeProof`. Each Dbc signs a challenge
+/// of the verifier, along with the set of Dbcs and `min`, to prove that it is held.
+///
+/// The proof alone does not show that the Dbcs exist: the src_tx of each of them is only
+/// checked to be consistent, which a made-up tx can be. Nor does it show that the Dbcs
+/// are unspent. For both, see `verify_unspent()`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ReservesProof {
+    pub entries: Vec<ReservesEntry>,
+    pub amount_proof: AmountRangeProof,
+}
+
+impl ReservesProof {
+    /// Prove that the Dbcs are worth at least `min` in total.
+    ///
+    /// The challenge is chosen by the verifier, so that the proof cannot be replayed.
+    pub fn prove(
+        dbcs: &[(Dbc, DerivedKey)],
+        min: Amount,
+        challenge: &[u8],
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<Self> {
+        let dbc_ids: Vec<DbcId> = dbcs.iter().map(|(dbc, _)| dbc.id()).collect();
+        let msg = Self::msg(&dbc_ids, min, challenge);
+
+        let mut total = RevealedAmount {
+            value: 0,
+            blinding_factor: Default::default(),
+        };
+        let mut entries = vec![];
+        for (dbc, derived_key) in dbcs.iter() {
+            if derived_key.dbc_id() != dbc.id() {
+                return Err(Error::DbcIdNotFound);
+            }
+            let revealed_amount = dbc.revealed_amount(derived_key)?;
+            total.value = total
+                .value
+                .checked_add(revealed_amount.value)
+                .ok_or(Error::ExcessiveTokenValue)?;
+            total.blinding_factor += revealed_amount.blinding_factor;
+
+            entries.push(ReservesEntry {
+                dbc_id: dbc.id(),
+                src_tx: dbc.src_tx.clone(),
+                signed_spends: dbc.signed_spends.clone(),
+                derived_key_sig: derived_key.sign(&msg),
+            });
+        }
+
+        Ok(Self {
+            entries,
+            amount_proof: AmountRangeProof::prove(&total, min.., rng)?,
+        })
+    }
+
+    /// The amount the Dbcs are proven to be worth at least.
+    pub fn min(&self) -> Amount {
+        self.amount_proof.min().unwrap_or_default()
+    }
+
+    /// Verify that the Dbcs are held, and worth at least `min()` in total, by their src_txs.
+    /// Those txs are checked to be consistent, but not against the spentbook.
+    pub fn verify(&self, challenge: &[u8]) -> Result<()> {
+        if self.entries.is_empty() || self.amount_proof.min().is_none() {
+            return Err(Error::EmptyReservesProof);
+        }
+        let dbc_ids: Vec<DbcId> = self.entries.iter().map(|e| e.dbc_id).collect();
+        if dbc_ids.iter().collect::<BTreeSet<_>>().len() != dbc_ids.len() {
+            return Err(Error::DbcIdNotUniqueInReserves);
+        }
+        let msg = Self::msg(&dbc_ids, self.min(), challenge);
+
+        // The Dbcs of an exchange are often outputs of the same tx.
+        let mut cache = VerifiedTxCache::new(DEFAULT_VERIFIED_TX_CACHE_CAPACITY);
+        let mut blinded_total = BlindedAmount::default();
+        for entry in self.entries.iter() {
+            if !entry.dbc_id.verify(&entry.derived_key_sig, &msg) {
+                return Err(Error::InvalidReservesSignature(entry.dbc_id));
+            }
+            TransactionVerifier::verify_with_cache(
+                &entry.src_tx,
+                &entry.signed_spends,
+                &mut cache,
+            )?;
+            blinded_total += entry
+                .src_tx
+                .outputs
+                .iter()
+                .find(|o| o.dbc_id() == &entry.dbc_id)
+                .ok_or(Error::BlindedOutputNotFound)?
+                .blinded_amount();
+        }
+
+        self.amount_proof.verify(blinded_total)
+    }
+
+    /// Like `verify()`, and also check with the spentbook that the src_tx of each Dbc was
+    /// logged, i.e. that each of its inputs was spent in exactly that tx, and that none of
+    /// the Dbcs is spent.
+    ///
+    /// `is_spent` tells if a DbcId is spent, and `is_spent_in` if it was spent in the tx
+    /// of the given hash.
+    pub fn verify_unspent(
+        &self,
+        challenge: &[u8],
+        is_spent: impl Fn(&DbcId) -> bool,
+        is_spent_in: impl Fn(&DbcId, Hash) -> bool,
+    ) -> Result<()> {
+        self.verify(challenge)?;
+        for entry in self.entries.iter() {
+            let src_tx_hash = entry.src_tx.hash();
+            if let Some(input) = entry
+                .src_tx
+                .inputs
+                .iter()
+                .find(|i| !is_spent_in(&i.dbc_id, src_tx_hash))
+            {
+                return Err(Error::ReservesInputNotSpentInSrcTx(input.dbc_id));
+            }
+        }
+        match self.entries.iter().find(|e| is_spent(&e.dbc_id)) {
+            Some(entry) => Err(Error::ReservesDbcSpent(entry.dbc_id)),
+            None => Ok(()),
+        }
+    }
+
+    /// The message signed by each Dbc.
+    fn msg(dbc_ids: &[DbcId], min: Amount, challenge: &[u8]) -> Vec<u8> {
+        let mut v: Vec<u8> = Default::default();
+        v.extend("reserves".as_bytes());
+        for dbc_id in dbc_ids.iter() {
+            v.extend(dbc_id.to_bytes().as_ref());
+        }
+        v.extend("min".as_bytes());
+        v.extend(min.to_le_bytes());
+        v.extend("challenge".as_bytes());
+        v.extend(Hash::hash(challenge).as_ref());
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, InputHistory, MainKey, NetworkId, RevealedInput, Token, TransactionBuilder};
+
+    #[test]
+    fn proves_reserves_without_revealing_amounts() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (mut spentbook_node, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis_single(&mut rng)?;
+
+        // The exchange holds 3 Dbcs, worth 600 in total.
+        let exchange_key = MainKey::random_from_rng(&mut rng);
+        let change = mock::GenesisMaterial::GENESIS_AMOUNT - 600;
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(
+                &genesis_dbc,
+                &genesis_dbc.derived_key(&genesis_material.main_key)?,
+            )?
+            .add_outputs([100, 200, 300].map(|amount| {
+                (
+                    Token::from_nano(amount),
+                    exchange_key.random_dbc_id_src(&mut rng),
+                )
+            }))
+            .add_output(
+                Token::from_nano(change),
+                genesis_material.main_key.random_dbc_id_src(&mut rng),
+            )
+            .build(Hash::default(), &mut rng)?;
+        spentbook_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
+        let reserves: Vec<(Dbc, DerivedKey)> = dbc_builder
+            .build()?
+            .into_iter()
+            .filter_map(|(dbc, _)| dbc.derived_key(&exchange_key).ok().map(|k| (dbc, k)))
+            .collect();
+        assert_eq!(reserves.len(), 3);
+
+        let challenge = b"auditor nonce";
+        let proof = ReservesProof::prove(&reserves, 600, challenge, &mut rng)?;
+        proof.verify(challenge)?;
+        proof.verify_unspent(
+            challenge,
+            |dbc_id| spentbook_node.is_spent(dbc_id),
+            |dbc_id, tx_hash| spentbook_node.is_spent_in(dbc_id, tx_hash),
+        )?;
+        assert_eq!(proof.min(), 600);
+
+        // The proof is for the challenge it was made for.
+        assert_eq!(
+            proof.verify(b"another nonce"),
+            Err(Error::InvalidReservesSignature(reserves[0].0.id()))
+        );
+        // A claim of more than the Dbcs are worth can not be proven.
+        assert_eq!(
+            ReservesProof::prove(&reserves, 601, challenge, &mut rng).map(|_| ()),
+            Err(Error::AmountNotInRange)
+        );
+        // Nor by counting a Dbc twice.
+        let mut doubled = proof.clone();
+        doubled.entries.push(doubled.entries[0].clone());
+        assert_eq!(
+            doubled.verify(challenge),
+            Err(Error::DbcIdNotUniqueInReserves)
+        );
+        // Nor by leaving a Dbc out.
+        let mut partial = proof.clone();
+        let _ = partial.entries.pop();
+        assert!(partial.verify(challenge).is_err());
+
+        // Once a Dbc is spent, the reserves are no longer held.
+        let (spent_dbc, derived_key) = &reserves[0];
+        let dbc_builder = TransactionBuilder::default()
+            .add_input_dbc(spent_dbc, derived_key)?
+            .add_output(
+                Token::from_nano(100),
+                exchange_key.random_dbc_id_src(&mut rng),
+            )
+            .build(Hash::default(), &mut rng)?;
+        spentbook_node.log_tx(&dbc_builder.spent_tx, &dbc_builder.signed_spends)?;
+        assert_eq!(
+            proof.verify_unspent(
+                challenge,
+                |dbc_id| spentbook_node.is_spent(dbc_id),
+                |dbc_id, tx_hash| spentbook_node.is_spent_in(dbc_id, tx_hash),
+            ),
+            Err(Error::ReservesDbcSpent(spent_dbc.id()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reserves_of_a_forged_src_tx_are_not_held() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (spentbook_node, _, _, _) = mock::GenesisBuilder::init_genesis_single(&mut rng)?;
+
+        // A src_tx minting tokens out of a made-up input, which is consistent on its own.
+        let forged_key = MainKey::random_from_rng(&mut rng).random_derived_key(&mut rng);
+        let forged_input = InputHistory {
+            input: RevealedInput::new(
+                forged_key.clone(),
+                RevealedAmount::from_amount(1_000_000, &mut rng),
+            ),
+            input_src_tx: DbcTransaction {
+                version: DbcTransaction::VERSION,
+                inputs: vec![],
+                outputs: vec![],
+                aggregated_signature: None,
+                network: NetworkId::Mainnet,
+            },
+        };
+        let exchange_key = MainKey::random_from_rng(&mut rng);
+        let reserves: Vec<(Dbc, DerivedKey)> = TransactionBuilder::default()
+            .add_input(forged_input)
+            .add_output(
+                Token::from_nano(1_000_000),
+                exchange_key.random_dbc_id_src(&mut rng),
+            )
+            .build(Hash::default(), &mut rng)?
+            .build()?
+            .into_iter()
+            .map(|(dbc, _)| {
+                let derived_key = dbc.derived_key(&exchange_key)?;
+                Ok((dbc, derived_key))
+            })
+            .collect::<Result<_>>()?;
+
+        let challenge = b"auditor nonce";
+        let proof = ReservesProof::prove(&reserves, 1_000_000, challenge, &mut rng)?;
+        proof.verify(challenge)?;
+
+        // The spentbook never logged the made-up input as spent in the src_tx.
+        assert_eq!(
+            proof.verify_unspent(
+                challenge,
+                |dbc_id| spentbook_node.is_spent(dbc_id),
+                |dbc_id, tx_hash| spentbook_node.is_spent_in(dbc_id, tx_hash),
+            ),
+            Err(Error::ReservesInputNotSpentInSrcTx(forged_key.dbc_id()))
+        );
+        Ok(())
+    }
+}
@@ -68,7 +68,19 @@ impl SignedSpend {
     /// Checks that the provided spent_tx_hash equals the input dst tx hash that was
     /// signed by the DerivedKey. Also verifies that that signature is
     /// valid for this SignedSpend.
+    ///
+    /// The spend is verified by the rules of its version.
     pub fn verify(&self, spent_tx_hash: Hash) -> Result<()> {
+        match self.spend.version {
+            Spend::LEGACY_VERSION | Spend::V1 => self.verify_v1(spent_tx_hash),
+            version => Err(Error::UnsupportedSpendVersion {
+                dbc_id: *self.dbc_id(),
+                version,
+            }),
+        }
+    }
+
+    fn verify_v1(&self, spent_tx_hash: Hash) -> Result<()> {
         // Verify that input spent_tx_hash matches self.spent_tx_hash which was signed by the DerivedKey of the input.
         if spent_tx_hash != self.spent_tx_hash() {
             return Err(Error::InvalidTransactionHash);
@@ -108,6 +120,8 @@ impl std::hash::Hash for SignedSpend {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Spend {
+    /// The version of the spend protocol, which defines how the spend is verified.
+    pub version: u16,
     /// DbcId of input Dbc that this SignedSpend is proving to be spent.
     pub dbc_id: DbcId,
     /// The transaction that the input Dbc is being spent in.
@@ -125,10 +139,25 @@ pub struct Spend {
 }
 
 impl Spend {
+    /// The version of the spends signed by this version of the crate.
+    /// Spends of older versions are still verified, as long as they are supported.
+    pub const VERSION: u16 = Self::V1;
+
+    /// The version of the spends of sn_dbc 19.1.0, which commit to neither their version
+    /// nor their network, see `DbcTransaction::LEGACY_VERSION`.
+    pub const LEGACY_VERSION: u16 = 0;
+
+    /// The first version of spends which commit to their version and network.
+    pub const V1: u16 = 1;
+
     /// Represent this Spend as bytes.
     /// There is no from_bytes, because this function is not symetric as it uses hashes
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes: Vec<u8> = Default::default();
+        if self.version != Self::LEGACY_VERSION {
+            bytes.extend(self.version.to_le_bytes());
+            bytes.extend(self.spent_tx.network.name().as_bytes());
+        }
         bytes.extend(self.dbc_id.to_bytes());
         bytes.extend(self.spent_tx.hash().as_ref());
         bytes.extend(self.reason.as_ref());
@@ -291,6 +291,7 @@ mod tests {
                     };
                     SignedSpend {
                         spend: Spend {
+                            version: Spend::VERSION,
                             dbc_id: *signed_spend.dbc_id(),
                             spent_tx: signed_spend.spend.spent_tx.clone(),
                             reason: Hash::default(),
@@ -365,17 +366,17 @@ mod tests {
     /// 3. modify b's revealed_amount.value to 2000, thereby creating b_fudged
     ///    (which a bad actor could pass to innocent recipient).
     /// 4. Check if the amounts match, using the provided API.
-    ///      Assert that APIs report that they do not match.
+    ///    Assert that APIs report that they do not match.
     /// 5. Create a tx with (b_fudged) as input, and Dbc (c) with amount 2000 as output.
     /// 6. Attempt to write this tx to the spentbook.
-    ///      This will fail because the input and output amounts are not equal.
+    ///    This will fail because the input and output amounts are not equal.
     /// 7. Force an invalid write to the spentbook
     /// 8. Attempt to write to spentbook again using the correct amount (1000).
-    ///      This will fail because b was already marked as spent in the spentbook.
-    ///      This demonstrates how an input can become burned if spentbook does
-    ///      not verify tx.
+    ///    This will fail because b was already marked as spent in the spentbook.
+    ///    This demonstrates how an input can become burned if spentbook does
+    ///    not verify tx.
     /// 9. Re-write spentbook correctly using the correct amount.
-    ///      Verify that the write succeeds.
+    ///    Verify that the write succeeds.
     #[test]
     fn test_mismatched_amount_and_blinded_amount() -> Result<(), Error> {
         // ----------
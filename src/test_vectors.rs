@@ -0,0 +1,327 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Known-answer test vectors of the byte layouts which the hashes and signatures
+//! of txs, spends and Dbcs are made over.
+//!
+//! The vectors are generated from a seeded rng and kept in `test_vectors/known_answers.json`,
+//! where other implementations can check their layouts against them. The test below
+//! regenerates them, and fails if they differ from the file. After a deliberate layout
+//! change, the file is rewritten with:
+//!
+//! ```text
+//! SN_DBC_WRITE_TEST_VECTORS=1 cargo test known_answers
+//! ```
+
+use crate::{
+    BlindedAmount, Dbc, DbcCiphers, DbcTransaction, DerivedKey, Hash, InputHistory, MainKey,
+    NetworkId, PublicKey, RevealedAmount, RevealedInput, SignedSpend, Token, TransactionBuilder,
+};
+
+use blsttc::rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+const VECTORS_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/test_vectors/known_answers.json"
+);
+const WRITE_VECTORS_VAR: &str = "SN_DBC_WRITE_TEST_VECTORS";
+const SEED: [u8; 32] = [7; 32];
+
+/// All hex encoded.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct KnownAnswers {
+    seed: String,
+    txs: Vec<TxVector>,
+    spends: Vec<SpendVector>,
+    ciphers: Vec<CiphersVector>,
+    dbcs: Vec<DbcVector>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct InputVector {
+    dbc_id: String,
+    blinded_amount: String,
+    signature: Option<String>,
+    to_bytes: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct OutputVector {
+    dbc_id: String,
+    range_proof: String,
+    blinded_amount: String,
+    to_bytes: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct TxVector {
+    name: String,
+    inputs: Vec<InputVector>,
+    outputs: Vec<OutputVector>,
+    aggregated_signature: Option<String>,
+    to_bytes: String,
+    hash: String,
+    /// The message signed by the inputs, see `DbcTransaction::serialize_tx()`.
+    serialize_tx: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SpendVector {
+    /// The secret key of the DbcId, which signs the spend.
+    derived_key: String,
+    dbc_id: String,
+    spent_tx_hash: String,
+    reason: String,
+    blinded_amount: String,
+    dbc_creation_tx_hash: String,
+    to_bytes: String,
+    hash: String,
+    derived_key_sig: String,
+    signed_spend_to_bytes: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct CiphersVector {
+    public_address: String,
+    derivation_index_cipher: String,
+    revealed_amount_cipher: String,
+    to_bytes: String,
+    hash: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct DbcVector {
+    src_tx_hash: String,
+    ciphers_to_bytes: String,
+    signed_spends_to_bytes: Vec<String>,
+    reason: String,
+    hash: String,
+}
+
+fn hex_blinded_amount(blinded_amount: &BlindedAmount) -> String {
+    hex::encode(blinded_amount.compress().to_bytes())
+}
+
+fn tx_vector(name: &str, tx: &DbcTransaction) -> TxVector {
+    TxVector {
+        name: name.to_string(),
+        inputs: tx
+            .inputs
+            .iter()
+            .map(|i| InputVector {
+                dbc_id: hex::encode(i.dbc_id.to_bytes()),
+                blinded_amount: hex_blinded_amount(&i.blinded_amount),
+                signature: i.signature.as_ref().map(|s| hex::encode(s.to_bytes())),
+                to_bytes: hex::encode(i.to_bytes()),
+            })
+            .collect(),
+        outputs: tx
+            .outputs
+            .iter()
+            .map(|o| OutputVector {
+                dbc_id: hex::encode(o.dbc_id().to_bytes()),
+                range_proof: hex::encode(o.range_proof().to_bytes()),
+                blinded_amount: hex_blinded_amount(&o.blinded_amount()),
+                to_bytes: hex::encode(o.to_bytes()),
+            })
+            .collect(),
+        aggregated_signature: tx
+            .aggregated_signature
+            .as_ref()
+            .map(|s| hex::encode(s.to_bytes())),
+        to_bytes: hex::encode(tx.to_bytes()),
+        hash: hex::encode(tx.hash().as_ref()),
+        serialize_tx: hex::encode(tx.serialize_tx()),
+    }
+}
+
+fn spend_vector(signed_spend: &SignedSpend, derived_key: &DerivedKey) -> SpendVector {
+    let spend = &signed_spend.spend;
+    SpendVector {
+        derived_key: hex::encode(derived_key.to_bytes()),
+        dbc_id: hex::encode(spend.dbc_id.to_bytes()),
+        spent_tx_hash: hex::encode(spend.spent_tx.hash().as_ref()),
+        reason: hex::encode(spend.reason.as_ref()),
+        blinded_amount: hex_blinded_amount(&spend.blinded_amount),
+        dbc_creation_tx_hash: hex::encode(spend.dbc_creation_tx.hash().as_ref()),
+        to_bytes: hex::encode(spend.to_bytes()),
+        hash: hex::encode(spend.hash().as_ref()),
+        derived_key_sig: hex::encode(signed_spend.derived_key_sig.to_bytes()),
+        signed_spend_to_bytes: hex::encode(signed_spend.to_bytes()),
+    }
+}
+
+fn ciphers_vector(ciphers: &DbcCiphers) -> CiphersVector {
+    CiphersVector {
+        public_address: hex::encode(ciphers.public_address.to_bytes()),
+        derivation_index_cipher: hex::encode(ciphers.derivation_index_cipher.to_bytes()),
+        revealed_amount_cipher: hex::encode(ciphers.revealed_amount_cipher.to_bytes()),
+        to_bytes: hex::encode(ciphers.to_bytes()),
+        hash: hex::encode(ciphers.hash().as_ref()),
+    }
+}
+
+fn dbc_vector(dbc: &Dbc) -> DbcVector {
+    DbcVector {
+        src_tx_hash: hex::encode(dbc.src_tx.hash().as_ref()),
+        ciphers_to_bytes: hex::encode(dbc.ciphers.to_bytes()),
+        signed_spends_to_bytes: dbc
+            .signed_spends
+            .iter()
+            .map(|s| hex::encode(s.to_bytes()))
+            .collect(),
+        reason: hex::encode(dbc.reason().as_ref()),
+        hash: hex::encode(dbc.hash().as_ref()),
+    }
+}
+
+/// Encrypt the ciphers of the Dbc again, with the given rng.
+///
+/// The builder encrypts with the thread rng, so the ciphers, and thus the hash of a
+/// Dbc, would otherwise differ from one run to the next.
+fn encrypt_ciphers_with_rng(
+    dbc: &mut Dbc,
+    main_key: &MainKey,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> crate::Result<()> {
+    let derivation_index = dbc.derivation_index(main_key)?;
+    let revealed_amount = dbc.revealed_amount(&dbc.derived_key(main_key)?)?;
+    let public_address = dbc.ciphers.public_address;
+
+    let public_key = PublicKey::from_bytes(public_address.to_bytes())?;
+    let derivation_index_cipher = public_key.encrypt_with_rng(rng, derivation_index);
+    let revealed_amount_cipher = public_address
+        .new_dbc_id(&derivation_index)
+        .public_key()
+        .encrypt_with_rng(rng, revealed_amount.to_bytes());
+
+    dbc.ciphers = DbcCiphers::from((
+        public_address,
+        derivation_index_cipher,
+        revealed_amount_cipher,
+    ));
+    Ok(())
+}
+
+/// Make a tx splitting a fictional input into two Dbcs, then merge those two into one
+/// with an aggregated signature, and record the layouts of all of it.
+fn generate() -> crate::Result<KnownAnswers> {
+    let mut rng = crate::rng::from_seed(SEED);
+    let reason = Hash::hash(b"known answers");
+
+    // A fictional input, like the input of the genesis tx.
+    let input_derived_key = MainKey::random_from_rng(&mut rng).random_derived_key(&mut rng);
+    let input_amount = 1_000;
+    let input = InputHistory {
+        input: RevealedInput::new(
+            input_derived_key.clone(),
+            RevealedAmount::from_amount(input_amount, &mut rng),
+        ),
+        input_src_tx: DbcTransaction {
+            version: DbcTransaction::VERSION,
+            inputs: vec![],
+            outputs: vec![],
+            aggregated_signature: None,
+            network: NetworkId::Mainnet,
+        },
+    };
+
+    let main_key = MainKey::random_from_rng(&mut rng);
+    let split = TransactionBuilder::default()
+        .add_input(input)
+        .add_output(Token::from_nano(300), main_key.random_dbc_id_src(&mut rng))
+        .add_output(Token::from_nano(700), main_key.random_dbc_id_src(&mut rng))
+        .build(reason, &mut rng)?;
+    let split_tx = split.spent_tx.clone();
+    let split_spends: Vec<SignedSpend> = split.signed_spends.iter().cloned().collect();
+    let mut split_dbcs: Vec<Dbc> = split.build()?.into_iter().map(|(dbc, _)| dbc).collect();
+
+    let mut merge = TransactionBuilder::default().aggregate_signatures();
+    for dbc in split_dbcs.iter() {
+        merge = merge.add_input_dbc(dbc, &dbc.derived_key(&main_key)?)?;
+    }
+    let merge = merge
+        .add_output(
+            Token::from_nano(1_000),
+            main_key.random_dbc_id_src(&mut rng),
+        )
+        .build(reason, &mut rng)?;
+    let merge_tx = merge.spent_tx.clone();
+    let merge_spends: Vec<SignedSpend> = merge.signed_spends.iter().cloned().collect();
+    let mut merge_dbcs: Vec<Dbc> = merge.build()?.into_iter().map(|(dbc, _)| dbc).collect();
+
+    let mut spends = vec![spend_vector(&split_spends[0], &input_derived_key)];
+    for signed_spend in merge_spends.iter() {
+        let dbc = split_dbcs
+            .iter()
+            .find(|dbc| dbc.id() == *signed_spend.dbc_id())
+            .ok_or(crate::Error::DbcIdNotFound)?;
+        spends.push(spend_vector(signed_spend, &dbc.derived_key(&main_key)?));
+    }
+
+    let mut dbcs = vec![];
+    for dbc in split_dbcs.iter_mut().chain(merge_dbcs.iter_mut()) {
+        encrypt_ciphers_with_rng(dbc, &main_key, &mut rng)?;
+        dbc.verify(&main_key)?;
+        dbcs.push(dbc.clone());
+    }
+
+    Ok(KnownAnswers {
+        seed: hex::encode(SEED),
+        txs: vec![
+            tx_vector("split, with input signatures", &split_tx),
+            tx_vector("merge, with an aggregated signature", &merge_tx),
+        ],
+        spends,
+        ciphers: dbcs
+            .iter()
+            .map(|dbc| ciphers_vector(&dbc.ciphers))
+            .collect(),
+        dbcs: dbcs.iter().map(dbc_vector).collect(),
+    })
+}
+
+#[test]
+fn known_answers() -> crate::Result<()> {
+    let generated = generate()?;
+    // The same seed gives the same vectors.
+    assert_eq!(generated, generate()?);
+
+    if std::env::var_os(WRITE_VECTORS_VAR).is_some() {
+        let json = serde_json::to_string_pretty(&generated).expect("Failed to encode vectors");
+        std::fs::write(VECTORS_PATH, json + "\n").expect("Failed to write vectors");
+    }
+
+    let json = std::fs::read_to_string(VECTORS_PATH).expect("Failed to read vectors");
+    let known: KnownAnswers = serde_json::from_str(&json).expect("Failed to decode vectors");
+    assert_eq!(
+        generated, known,
+        "A byte layout has changed. If that is intended, set {} to rewrite the vectors.",
+        WRITE_VECTORS_VAR
+    );
+    Ok(())
+}
+
+#[test]
+fn known_answers_spend_signatures_are_reproducible() -> crate::Result<()> {
+    // BLS signatures are deterministic, so the vectors also pin the signing of spends.
+    for spend in generate()?.spends.iter() {
+        let secret_key: [u8; 32] = hex::decode(&spend.derived_key)
+            .expect("Failed to decode key")
+            .try_into()
+            .expect("A secret key is 32 bytes");
+        let derived_key = DerivedKey::new(blsttc::SecretKey::from_bytes(secret_key)?);
+        assert_eq!(hex::encode(derived_key.dbc_id().to_bytes()), spend.dbc_id);
+
+        let sig = derived_key.sign(&hex::decode(&spend.to_bytes).expect("Failed to decode spend"));
+        assert_eq!(hex::encode(sig.to_bytes()), spend.derived_key_sig);
+    }
+    Ok(())
+}
@@ -0,0 +1,307 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! quickcheck generators for the public types of this crate, for property tests
+//! of this crate and of the crates using it.
+//!
+//! The Dbcs are built from a genesis Dbc made with `mock::GenesisBuilder`, so they
+//! are valid, and logged to a spentbook. `CorruptDbc` then breaks a valid Dbc in a
+//! known way, for testing that the breakage is caught.
+//!
+//! All randomness is drawn from the quickcheck `Gen`, so a failing case is
+//! reproduced by quickcheck like any other.
+
+use crate::{
+    mock, rand::rngs::StdRng, rand::SeedableRng, Dbc, DbcId, DbcTransaction, DerivedKey, Hash,
+    MainKey, PublicAddress, SignedSpend, Token, TransactionBuilder,
+};
+
+use quickcheck::{Arbitrary, Gen};
+
+/// The most inputs a generated Dbc is merged from.
+const MAX_INPUTS: u8 = 4;
+
+/// An rng seeded from the quickcheck Gen.
+pub fn rng(g: &mut Gen) -> StdRng {
+    StdRng::from_seed(std::array::from_fn(|_| u8::arbitrary(g)))
+}
+
+impl Arbitrary for Token {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Token::from_nano(u64::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.as_nano().shrink().map(Token::from_nano))
+    }
+}
+
+impl Arbitrary for MainKey {
+    fn arbitrary(g: &mut Gen) -> Self {
+        MainKey::random_from_rng(&mut rng(g))
+    }
+}
+
+impl Arbitrary for DerivedKey {
+    fn arbitrary(g: &mut Gen) -> Self {
+        MainKey::arbitrary(g).random_derived_key(&mut rng(g))
+    }
+}
+
+impl Arbitrary for DbcId {
+    fn arbitrary(g: &mut Gen) -> Self {
+        DerivedKey::arbitrary(g).dbc_id()
+    }
+}
+
+impl Arbitrary for PublicAddress {
+    fn arbitrary(g: &mut Gen) -> Self {
+        MainKey::arbitrary(g).public_address()
+    }
+}
+
+/// A valid Dbc, along with the MainKey it was sent to, and the spentbook
+/// which logged the spends of its inputs.
+#[derive(Debug, Clone)]
+pub struct ValidDbc {
+    pub dbc: Dbc,
+    pub main_key: MainKey,
+    pub amount: Token,
+    pub spentbook_node: mock::SpentbookNode,
+}
+
+impl ValidDbc {
+    /// Split the genesis Dbc into `input_count` Dbcs, then merge them into a Dbc of
+    /// `amount` and a Dbc of the change. The Dbc of `amount` is returned.
+    ///
+    /// The tx of the returned Dbc thus always has `input_count` inputs and two outputs.
+    pub fn new(input_count: usize, amount: Token, rng: &mut StdRng) -> Self {
+        let (mut spentbook_node, genesis_dbc, genesis_material, _) =
+            mock::GenesisBuilder::init_genesis_single(&mut *rng).expect("Failed to init genesis");
+        let total = mock::GenesisMaterial::GENESIS_AMOUNT;
+
+        let main_key = MainKey::random_from_rng(&mut *rng);
+        let input_count = input_count.max(1) as u64;
+        let split_amounts = (0..input_count).map(|i| {
+            let share = total / input_count;
+            if i == 0 {
+                share + total % input_count
+            } else {
+                share
+            }
+        });
+        let split = TransactionBuilder::default()
+            .add_input_dbc(
+                &genesis_dbc,
+                &genesis_dbc
+                    .derived_key(&genesis_material.main_key)
+                    .expect("Failed to get genesis key"),
+            )
+            .expect("Failed to add genesis input")
+            .add_outputs(
+                split_amounts
+                    .map(|amount| (Token::from_nano(amount), main_key.random_dbc_id_src(rng))),
+            )
+            .build(Hash::default(), &mut *rng)
+            .expect("Failed to build split tx");
+        spentbook_node
+            .log_tx(&split.spent_tx, &split.signed_spends)
+            .expect("Failed to log split tx");
+
+        let mut merge = TransactionBuilder::default();
+        for (dbc, _) in split.build().expect("Failed to build split dbcs") {
+            let derived_key = dbc.derived_key(&main_key).expect("Failed to get key");
+            merge = merge
+                .add_input_dbc(&dbc, &derived_key)
+                .expect("Failed to add input");
+        }
+        let merge = merge
+            .add_output(amount, main_key.random_dbc_id_src(rng))
+            .add_output(
+                Token::from_nano(total - amount.as_nano()),
+                main_key.random_dbc_id_src(rng),
+            )
+            .build(Hash::default(), &mut *rng)
+            .expect("Failed to build merge tx");
+        spentbook_node
+            .log_tx(&merge.spent_tx, &merge.signed_spends)
+            .expect("Failed to log merge tx");
+
+        let (dbc, _) = merge
+            .build()
+            .expect("Failed to build merge dbcs")
+            .into_iter()
+            .find(|(dbc, _)| {
+                dbc.revealed_amount(&dbc.derived_key(&main_key).expect("Failed to get key"))
+                    .map(|a| a.value() == amount.as_nano())
+                    .unwrap_or(false)
+            })
+            .expect("Failed to find the dbc of the amount");
+
+        Self {
+            dbc,
+            main_key,
+            amount,
+            spentbook_node,
+        }
+    }
+}
+
+impl Arbitrary for ValidDbc {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let input_count = 1 + (u8::arbitrary(g) % MAX_INPUTS) as usize;
+        let amount = Token::arbitrary(g);
+        Self::new(input_count, amount, &mut rng(g))
+    }
+}
+
+impl Arbitrary for Dbc {
+    fn arbitrary(g: &mut Gen) -> Self {
+        ValidDbc::arbitrary(g).dbc
+    }
+}
+
+impl Arbitrary for DbcTransaction {
+    fn arbitrary(g: &mut Gen) -> Self {
+        ValidDbc::arbitrary(g).dbc.src_tx
+    }
+}
+
+impl Arbitrary for SignedSpend {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let dbc = ValidDbc::arbitrary(g).dbc;
+        let signed_spends = dbc.signed_spends.into_iter().collect::<Vec<_>>();
+        g.choose(&signed_spends)
+            .cloned()
+            .expect("A valid dbc has at least one signed spend")
+    }
+}
+
+/// A way a valid Dbc is broken by `CorruptDbc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corruption {
+    /// The outputs of the tx are reordered, which breaks their range proofs.
+    ReorderedOutputs,
+    /// An output of the tx appears twice.
+    DuplicatedOutput,
+    /// A SignedSpend of the Dbc is removed.
+    MissingSignedSpend,
+    /// A SignedSpend is signed by a key other than the DerivedKey of the spent Dbc.
+    ForgedSpendSignature,
+    /// The Dbc is checked with a MainKey it was not sent to.
+    WrongMainKey,
+}
+
+impl Corruption {
+    pub const ALL: [Corruption; 5] = [
+        Self::ReorderedOutputs,
+        Self::DuplicatedOutput,
+        Self::MissingSignedSpend,
+        Self::ForgedSpendSignature,
+        Self::WrongMainKey,
+    ];
+}
+
+impl Arbitrary for Corruption {
+    fn arbitrary(g: &mut Gen) -> Self {
+        *g.choose(&Self::ALL).expect("Corruption::ALL is not empty")
+    }
+}
+
+/// A Dbc which fails to verify with `main_key`, for the reason given by `corruption`.
+#[derive(Debug, Clone)]
+pub struct CorruptDbc {
+    pub dbc: Dbc,
+    pub main_key: MainKey,
+    pub corruption: Corruption,
+}
+
+impl CorruptDbc {
+    /// Break the valid Dbc in the given way.
+    pub fn new(valid: &ValidDbc, corruption: Corruption, rng: &mut StdRng) -> Self {
+        let mut dbc = valid.dbc.clone();
+        let mut main_key = valid.main_key.clone();
+
+        match corruption {
+            Corruption::ReorderedOutputs => dbc.src_tx.outputs.swap(0, 1),
+            Corruption::DuplicatedOutput => {
+                let output = dbc.src_tx.outputs[0].clone();
+                dbc.src_tx.outputs.push(output);
+            }
+            Corruption::MissingSignedSpend => {
+                let _ = dbc.signed_spends.pop_first();
+            }
+            Corruption::ForgedSpendSignature => {
+                let mut signed_spend = dbc
+                    .signed_spends
+                    .pop_first()
+                    .expect("A valid dbc has at least one signed spend");
+                let forger = MainKey::random_from_rng(rng).random_derived_key(rng);
+                signed_spend.derived_key_sig = forger.sign(&signed_spend.spend.to_bytes());
+                let _ = dbc.signed_spends.insert(signed_spend);
+            }
+            Corruption::WrongMainKey => main_key = MainKey::random_from_rng(rng),
+        }
+
+        Self {
+            dbc,
+            main_key,
+            corruption,
+        }
+    }
+}
+
+impl Arbitrary for CorruptDbc {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let valid = ValidDbc::arbitrary(g);
+        let corruption = Corruption::arbitrary(g);
+        Self::new(&valid, corruption, &mut rng(g))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, TransactionVerifier};
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn prop_valid_dbc_verifies(valid: ValidDbc) -> Result<(), Error> {
+        valid.dbc.verify(&valid.main_key)?;
+        TransactionVerifier::verify(&valid.dbc.src_tx, &valid.dbc.signed_spends)?;
+
+        let revealed_amount = valid
+            .dbc
+            .revealed_amount(&valid.dbc.derived_key(&valid.main_key)?)?;
+        assert_eq!(revealed_amount.value(), valid.amount.as_nano());
+        for signed_spend in valid.dbc.signed_spends.iter() {
+            assert!(valid.spentbook_node.is_spent(signed_spend.dbc_id()));
+        }
+        Ok(())
+    }
+
+    #[quickcheck]
+    fn prop_corrupt_dbc_fails_to_verify(corrupt: CorruptDbc) {
+        assert!(corrupt.dbc.verify(&corrupt.main_key).is_err());
+        assert!(!corrupt.dbc.verify_report(&corrupt.main_key).is_ok());
+    }
+
+    #[test]
+    fn every_corruption_fails_to_verify() {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let valid = ValidDbc::new(2, Token::from_nano(100), &mut rng);
+        for corruption in Corruption::ALL {
+            let corrupt = CorruptDbc::new(&valid, corruption, &mut rng);
+            assert!(
+                corrupt.dbc.verify(&corrupt.main_key).is_err(),
+                "{:?} was not caught",
+                corruption
+            );
+        }
+    }
+}
@@ -0,0 +1,554 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Formatting and parsing of `Token` amounts for display to users.
+//!
+//! `Token`'s own `Display` and `FromStr` stay as they are, with 9 decimals. A
+//! `TokenFormat` adds units, a symbol, thousands separators and trimming of trailing
+//! zeros, and parses amounts back either strictly, as it formats them, or leniently,
+//! as users type them.
+
+use crate::Token;
+
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// An error of parsing a `Token` amount with a `TokenFormat`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TokenParseError {
+    #[error("The amount is empty.")]
+    Empty,
+    #[error("Unexpected character {character:?} at index {index}.")]
+    InvalidCharacter { character: char, index: usize },
+    #[error("The amount has no digits before or after its decimal point.")]
+    MissingDigits,
+    #[error("Thousands separator out of place at index {index}.")]
+    MisplacedSeparator { index: usize },
+    #[error("The amount does not end with the symbol {0:?}.")]
+    MissingSymbol(String),
+    #[error("Unknown unit {0:?}.")]
+    UnknownUnit(String),
+    #[error("More than the {max} decimals of the unit {unit}.")]
+    TooManyDecimals { unit: TokenUnit, max: u32 },
+    #[error("The amount exceeds the maximum value (u64::MAX nanos).")]
+    Overflow,
+}
+
+/// A named unit of `Token` amounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum TokenUnit {
+    /// 1_000_000_000 nanos.
+    #[default]
+    Token,
+    /// 1_000_000 nanos.
+    Milli,
+    /// 1_000 nanos.
+    Micro,
+    /// The smallest unit.
+    Nano,
+}
+
+impl TokenUnit {
+    /// The number of decimals of an amount in this unit.
+    pub const fn decimals(self) -> u32 {
+        match self {
+            Self::Token => 9,
+            Self::Milli => 6,
+            Self::Micro => 3,
+            Self::Nano => 0,
+        }
+    }
+
+    /// The number of nanos in one of this unit.
+    pub const fn nanos(self) -> u64 {
+        10u64.pow(self.decimals())
+    }
+}
+
+impl fmt::Display for TokenUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Token => "token",
+            Self::Milli => "milli",
+            Self::Micro => "micro",
+            Self::Nano => "nano",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for TokenUnit {
+    type Err = TokenParseError;
+
+    /// Parse a unit name, in any case, singular or plural.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_lowercase();
+        match name.strip_suffix('s').unwrap_or(&name) {
+            "token" => Ok(Self::Token),
+            "milli" => Ok(Self::Milli),
+            "micro" => Ok(Self::Micro),
+            "nano" => Ok(Self::Nano),
+            _ => Err(TokenParseError::UnknownUnit(s.to_string())),
+        }
+    }
+}
+
+/// How to format, and parse, `Token` amounts.
+///
+/// The default formats an amount in tokens, with trailing zeros trimmed, no symbol
+/// and no thousands separator, e.g. `1234.5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenFormat {
+    unit: TokenUnit,
+    symbol: Option<String>,
+    thousands_separator: Option<char>,
+    trim_trailing_zeros: bool,
+}
+
+impl Default for TokenFormat {
+    fn default() -> Self {
+        Self {
+            unit: TokenUnit::Token,
+            symbol: None,
+            thousands_separator: None,
+            trim_trailing_zeros: true,
+        }
+    }
+}
+
+impl TokenFormat {
+    /// Format amounts in the given unit.
+    pub fn unit(mut self, unit: TokenUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Follow amounts by a space and the given symbol, e.g. `1.5 SNT`.
+    pub fn symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.symbol = Some(symbol.into());
+        self
+    }
+
+    /// Separate the thousands of the whole part of amounts, e.g. `1,234.5`.
+    pub fn thousands_separator(mut self, separator: char) -> Self {
+        self.thousands_separator = Some(separator);
+        self
+    }
+
+    /// Whether to trim the trailing zeros of the decimals, which is the default.
+    /// Untrimmed, an amount has all the decimals of its unit, e.g. `1.500000000`.
+    pub fn trim_trailing_zeros(mut self, trim: bool) -> Self {
+        self.trim_trailing_zeros = trim;
+        self
+    }
+
+    /// Format the amount.
+    pub fn format(&self, token: Token) -> String {
+        let nanos = self.unit.nanos();
+        let whole = (token.as_nano() / nanos).to_string();
+        let decimals = match self.unit.decimals() {
+            0 => String::new(),
+            width => format!(
+                "{:0width$}",
+                token.as_nano() % nanos,
+                width = width as usize
+            ),
+        };
+        let decimals = if self.trim_trailing_zeros {
+            decimals.trim_end_matches('0')
+        } else {
+            &decimals
+        };
+
+        let mut s = String::new();
+        for (i, digit) in whole.chars().enumerate() {
+            if let Some(separator) = self.thousands_separator {
+                if i > 0 && (whole.len() - i) % 3 == 0 {
+                    s.push(separator);
+                }
+            }
+            s.push(digit);
+        }
+        if !decimals.is_empty() {
+            s.push('.');
+            s.push_str(decimals);
+        }
+        if let Some(symbol) = &self.symbol {
+            s.push(' ');
+            s.push_str(symbol);
+        }
+        s
+    }
+
+    /// Parse an amount in the form this format gives it.
+    ///
+    /// The amount is in the unit of this format, ends with the symbol if there is one,
+    /// and has its thousands separated if there is a separator. Trailing zeros of the
+    /// decimals are accepted, whether or not this format trims them.
+    pub fn parse_strict(&self, s: &str) -> Result<Token, TokenParseError> {
+        if s.is_empty() {
+            return Err(TokenParseError::Empty);
+        }
+        let amount = match &self.symbol {
+            Some(symbol) => s
+                .strip_suffix(symbol.as_str())
+                .and_then(|s| s.strip_suffix(' '))
+                .ok_or_else(|| TokenParseError::MissingSymbol(symbol.clone()))?,
+            None => s,
+        };
+
+        let (whole, decimals) = match amount.split_once('.') {
+            Some((whole, decimals)) => (whole, Some(decimals)),
+            None => (amount, None),
+        };
+        if whole.is_empty() || decimals == Some("") {
+            return Err(TokenParseError::MissingDigits);
+        }
+
+        let whole_digits = match self.thousands_separator {
+            Some(separator) => {
+                let mut digits = String::new();
+                let groups: Vec<&str> = whole.split(separator).collect();
+                let mut index = 0;
+                for (i, group) in groups.iter().enumerate() {
+                    let misplaced = if i == 0 {
+                        group.is_empty() || (groups.len() > 1 && group.len() > 3)
+                    } else {
+                        group.len() != 3
+                    };
+                    if misplaced {
+                        // The separator before or after this group is out of place.
+                        let index = if i == 0 { group.len() } else { index - 1 };
+                        return Err(TokenParseError::MisplacedSeparator { index });
+                    }
+                    check_digits(group, index)?;
+                    digits.push_str(group);
+                    index += group.len() + separator.len_utf8();
+                }
+                digits
+            }
+            None => {
+                check_digits(whole, 0)?;
+                whole.to_string()
+            }
+        };
+        if let Some(decimals) = decimals {
+            check_digits(decimals, whole.len() + 1)?;
+        }
+
+        to_token(&whole_digits, decimals.unwrap_or_default(), self.unit)
+    }
+
+    /// Parse an amount as a user may type it.
+    ///
+    /// Surrounding whitespace, the symbol, underscores and the thousands separator are
+    /// ignored wherever they are. The amount may be followed by the name of a unit, e.g.
+    /// `250 milli`, else it is in the unit of this format. Either side of the decimal
+    /// point may be left out, as in `.5` or `5.`.
+    pub fn parse_lenient(&self, s: &str) -> Result<Token, TokenParseError> {
+        let mut amount = s.trim();
+        if let Some(symbol) = &self.symbol {
+            amount = amount
+                .strip_suffix(symbol.as_str())
+                .unwrap_or(amount)
+                .trim();
+        }
+        if amount.is_empty() {
+            return Err(TokenParseError::Empty);
+        }
+
+        let mut unit = self.unit;
+        if let Some((number, name)) = amount.rsplit_once(char::is_whitespace) {
+            if name.chars().all(char::is_alphabetic) {
+                unit = name.parse()?;
+                amount = number.trim_end();
+            }
+        }
+
+        let offset = amount.as_ptr() as usize - s.as_ptr() as usize;
+        let mut whole = String::new();
+        let mut decimals: Option<String> = None;
+        for (i, c) in amount.char_indices() {
+            match c {
+                '0'..='9' => decimals.as_mut().unwrap_or(&mut whole).push(c),
+                '.' if decimals.is_none() => decimals = Some(String::new()),
+                '_' => {}
+                c if Some(c) == self.thousands_separator => {}
+                character => {
+                    return Err(TokenParseError::InvalidCharacter {
+                        character,
+                        index: offset + i,
+                    })
+                }
+            }
+        }
+        let decimals = decimals.unwrap_or_default();
+        if whole.is_empty() && decimals.is_empty() {
+            return Err(TokenParseError::MissingDigits);
+        }
+
+        to_token(&whole, &decimals, unit)
+    }
+}
+
+fn check_digits(s: &str, offset: usize) -> Result<(), TokenParseError> {
+    match s.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((i, character)) => Err(TokenParseError::InvalidCharacter {
+            character,
+            index: offset + i,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The Token of the given ascii digits of the whole part and decimals, in the unit.
+fn to_token(whole: &str, decimals: &str, unit: TokenUnit) -> Result<Token, TokenParseError> {
+    let decimals = decimals.trim_end_matches('0');
+    let max = unit.decimals();
+    if decimals.len() > max as usize {
+        return Err(TokenParseError::TooManyDecimals { unit, max });
+    }
+
+    let mut nanos: u64 = 0;
+    for digit in whole.bytes().chain(decimals.bytes()) {
+        nanos = nanos
+            .checked_mul(10)
+            .and_then(|n| n.checked_add(u64::from(digit - b'0')))
+            .ok_or(TokenParseError::Overflow)?;
+    }
+    nanos
+        .checked_mul(10u64.pow(max - decimals.len() as u32))
+        .map(Token::from_nano)
+        .ok_or(TokenParseError::Overflow)
+}
+
+/// Serde adapters to serialize a `Token` as a decimal string of tokens, e.g. `"1.5"`,
+/// rather than as its number of nanos:
+///
+/// ```
+/// # use serde::{Deserialize, Serialize};
+/// # use sn_dbc::Token;
+/// #[derive(Serialize, Deserialize)]
+/// struct Payment {
+///     #[serde(with = "sn_dbc::token_decimal")]
+///     amount: Token,
+///     #[serde(with = "sn_dbc::token_decimal::option")]
+///     fee: Option<Token>,
+/// }
+/// ```
+///
+/// The string is parsed strictly, with the default `TokenFormat`.
+#[cfg(feature = "serde")]
+pub mod token_decimal {
+    use super::TokenFormat;
+    use crate::Token;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(token: &Token, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&TokenFormat::default().format(*token))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Token, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        TokenFormat::default()
+            .parse_strict(&s)
+            .map_err(D::Error::custom)
+    }
+
+    /// The same, for an optional `Token`.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        use crate::Token;
+
+        pub fn serialize<S: Serializer>(
+            token: &Option<Token>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match token {
+                Some(token) => super::serialize(token, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Token>, D::Error> {
+            #[derive(Deserialize)]
+            struct Decimal(#[serde(with = "super")] Token);
+
+            Ok(Option::<Decimal>::deserialize(deserializer)?.map(|d| d.0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn format() {
+        let format = TokenFormat::default();
+        assert_eq!(format.format(Token::zero()), "0");
+        assert_eq!(format.format(Token::from_nano(1)), "0.000000001");
+        assert_eq!(format.format(Token::from_nano(1_500_000_000)), "1.5");
+        assert_eq!(
+            format.format(Token::from_nano(u64::MAX)),
+            "18446744073.709551615"
+        );
+
+        let format = TokenFormat::default()
+            .symbol("SNT")
+            .thousands_separator(',');
+        assert_eq!(
+            format.format(Token::from_nano(1_234_567_500_000_000)),
+            "1,234,567.5 SNT"
+        );
+        assert_eq!(format.format(Token::from_nano(123_000_000_000)), "123 SNT");
+
+        let format = TokenFormat::default()
+            .unit(TokenUnit::Milli)
+            .trim_trailing_zeros(false);
+        assert_eq!(
+            format.format(Token::from_nano(1_500_000_000)),
+            "1500.000000"
+        );
+        let format = format.unit(TokenUnit::Nano);
+        assert_eq!(format.format(Token::from_nano(1_500)), "1500");
+    }
+
+    #[test]
+    fn parse_strict() {
+        let format = TokenFormat::default()
+            .symbol("SNT")
+            .thousands_separator(',');
+        let parse = |s| format.parse_strict(s);
+        assert_eq!(
+            parse("1,234.5 SNT"),
+            Ok(Token::from_nano(1_234_500_000_000))
+        );
+        assert_eq!(parse("0.50 SNT"), Ok(Token::from_nano(500_000_000)));
+        assert_eq!(parse("123 SNT"), Ok(Token::from_nano(123_000_000_000)));
+
+        assert_eq!(parse(""), Err(TokenParseError::Empty));
+        assert_eq!(
+            parse("1,234.5"),
+            Err(TokenParseError::MissingSymbol("SNT".to_string()))
+        );
+        assert_eq!(
+            parse("1234,5 SNT"),
+            Err(TokenParseError::MisplacedSeparator { index: 4 })
+        );
+        assert_eq!(
+            parse("1,2345 SNT"),
+            Err(TokenParseError::MisplacedSeparator { index: 1 })
+        );
+        assert_eq!(
+            parse("1,234.x SNT"),
+            Err(TokenParseError::InvalidCharacter {
+                character: 'x',
+                index: 6
+            })
+        );
+        assert_eq!(parse(".5 SNT"), Err(TokenParseError::MissingDigits));
+        assert_eq!(parse("5. SNT"), Err(TokenParseError::MissingDigits));
+        assert_eq!(
+            parse("0.0000000001 SNT"),
+            Err(TokenParseError::TooManyDecimals {
+                unit: TokenUnit::Token,
+                max: 9
+            })
+        );
+        assert_eq!(parse("18,446,744,074 SNT"), Err(TokenParseError::Overflow));
+    }
+
+    #[test]
+    fn parse_lenient() {
+        let format = TokenFormat::default()
+            .symbol("SNT")
+            .thousands_separator(',');
+        let parse = |s| format.parse_lenient(s);
+        assert_eq!(parse(" 1234.5 "), Ok(Token::from_nano(1_234_500_000_000)));
+        assert_eq!(parse("1,234.5SNT"), Ok(Token::from_nano(1_234_500_000_000)));
+        assert_eq!(parse("1_000"), Ok(Token::from_nano(1_000_000_000_000)));
+        assert_eq!(parse(".5"), Ok(Token::from_nano(500_000_000)));
+        assert_eq!(parse("5."), Ok(Token::from_nano(5_000_000_000)));
+        assert_eq!(parse("250 milli"), Ok(Token::from_nano(250_000_000)));
+        assert_eq!(parse("1.5 Micros"), Ok(Token::from_nano(1_500)));
+        assert_eq!(parse("7 nano SNT"), Ok(Token::from_nano(7)));
+
+        assert_eq!(parse("  "), Err(TokenParseError::Empty));
+        assert_eq!(parse("."), Err(TokenParseError::MissingDigits));
+        assert_eq!(
+            parse("5 kilo"),
+            Err(TokenParseError::UnknownUnit("kilo".to_string()))
+        );
+        assert_eq!(
+            parse(" 1.2.3"),
+            Err(TokenParseError::InvalidCharacter {
+                character: '.',
+                index: 4
+            })
+        );
+        assert_eq!(
+            parse("1.5 nano"),
+            Err(TokenParseError::TooManyDecimals {
+                unit: TokenUnit::Nano,
+                max: 0
+            })
+        );
+    }
+
+    #[quickcheck]
+    fn prop_format_parse_round_trip(nanos: u64, unit: u8, separator: bool, trim: bool) -> bool {
+        let unit = [
+            TokenUnit::Token,
+            TokenUnit::Milli,
+            TokenUnit::Micro,
+            TokenUnit::Nano,
+        ][unit as usize % 4];
+        let mut format = TokenFormat::default()
+            .unit(unit)
+            .symbol("SNT")
+            .trim_trailing_zeros(trim);
+        if separator {
+            format = format.thousands_separator('\'');
+        }
+
+        let token = Token::from_nano(nanos);
+        let s = format.format(token);
+        format.parse_strict(&s) == Ok(token) && format.parse_lenient(&s) == Ok(token)
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn token_decimal() -> Result<(), serde_json::Error> {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Payment {
+            #[serde(with = "token_decimal")]
+            amount: Token,
+            #[serde(with = "token_decimal::option")]
+            fee: Option<Token>,
+        }
+
+        let payment = Payment {
+            amount: Token::from_nano(1_500_000_000),
+            fee: None,
+        };
+        let json = serde_json::to_string(&payment)?;
+        assert_eq!(json, r#"{"amount":"1.5","fee":null}"#);
+        assert_eq!(serde_json::from_str::<Payment>(&json)?, payment);
+
+        let payment: Payment = serde_json::from_str(r#"{"amount":"2","fee":"0.000000001"}"#)?;
+        assert_eq!(payment.fee, Some(Token::from_nano(1)));
+        assert!(serde_json::from_str::<Payment>(r#"{"amount":"1,5","fee":null}"#).is_err());
+        Ok(())
+    }
+}
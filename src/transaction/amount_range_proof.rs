@@ -0,0 +1,222 @@
+// Copyright (c) 2023, MaidSafe.
+// All rights reserved.
+//
+// This SAFE Network Software is licensed under the BSD-3-Clause license.
+// Please see the LICENSE file for more details.
+
+//! A proof that a blinded amount lies within public bounds, without revealing it.
+//!
+//! The blinded amount is the commitment `C = v*B + r*B'` of the amount `v` with the
+//! blinding factor `r`. For a lower bound `a`, `C - a*B` is a commitment of `v - a`
+//! with the same blinding factor, and a range proof that `v - a` is in [0, 2^64)
+//! proves that `v >= a`. For an upper bound `b`, `b*B - C` is a commitment of `b - v`
+//! with the blinding factor `-r`, and its range proof proves that `v <= b`.
+//! Anyone holding the blinded amount can compute these commitments, and verify.
+
+use super::{Amount, RevealedAmount, RANGE_PROOF_BITS, RANGE_PROOF_PARTIES};
+use crate::{
+    rand::{CryptoRng, RngCore},
+    BlindedAmount, Error, Result,
+};
+
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use std::ops::{Bound, RangeBounds};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const TRANSCRIPT_LABEL: &[u8] = b"SN_DBC amount range";
+
+/// A proof that the amount of a `BlindedAmount` is at least `min`, and/or at most `max`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct AmountRangeProof {
+    min: Option<(Amount, RangeProof)>,
+    max: Option<(Amount, RangeProof)>,
+}
+
+impl AmountRangeProof {
+    /// Prove that the amount is within the range, e.g. `100..` for at least 100,
+    /// `..=500` for at most 500, or `100..=500` for both.
+    ///
+    /// Fails if the amount is not within the range, or the range is unbounded.
+    pub fn prove(
+        revealed_amount: &RevealedAmount,
+        range: impl RangeBounds<Amount>,
+        mut rng: impl RngCore + CryptoRng,
+    ) -> Result<Self> {
+        let min = match range.start_bound() {
+            Bound::Included(min) => Some(*min),
+            Bound::Excluded(min) => Some(min.checked_add(1).ok_or(Error::AmountNotInRange)?),
+            Bound::Unbounded => None,
+        };
+        let max = match range.end_bound() {
+            Bound::Included(max) => Some(*max),
+            Bound::Excluded(max) => Some(max.checked_sub(1).ok_or(Error::AmountNotInRange)?),
+            Bound::Unbounded => None,
+        };
+        if min.is_none() && max.is_none() {
+            return Err(Error::UnboundedAmountRange);
+        }
+
+        let value = revealed_amount.value;
+        let blinding_factor = revealed_amount.blinding_factor;
+        let min = match min {
+            Some(min) => {
+                let excess = value.checked_sub(min).ok_or(Error::AmountNotInRange)?;
+                let proof = prove(excess, blinding_factor, Side::Min, min, &mut rng)?;
+                Some((min, proof))
+            }
+            None => None,
+        };
+        let max = match max {
+            Some(max) => {
+                let headroom = max.checked_sub(value).ok_or(Error::AmountNotInRange)?;
+                let proof = prove(headroom, -blinding_factor, Side::Max, max, &mut rng)?;
+                Some((max, proof))
+            }
+            None => None,
+        };
+
+        Ok(Self { min, max })
+    }
+
+    /// The lower bound that the amount is proven to be at or above, if any.
+    pub fn min(&self) -> Option<Amount> {
+        self.min.as_ref().map(|(min, _)| *min)
+    }
+
+    /// The upper bound that the amount is proven to be at or below, if any.
+    pub fn max(&self) -> Option<Amount> {
+        self.max.as_ref().map(|(max, _)| *max)
+    }
+
+    /// Verify that the amount of the blinded amount is within the bounds of this proof.
+    pub fn verify(&self, blinded_amount: BlindedAmount) -> Result<()> {
+        let pc_gens = PedersenGens::default();
+        if let Some((min, proof)) = &self.min {
+            let excess = blinded_amount - pc_gens.B * Scalar::from(*min);
+            verify(proof, excess, Side::Min, *min)?;
+        }
+        if let Some((max, proof)) = &self.max {
+            let headroom = pc_gens.B * Scalar::from(*max) - blinded_amount;
+            verify(proof, headroom, Side::Max, *max)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Min,
+    Max,
+}
+
+/// The transcript of the proof for the bound, which the proof is thus bound to.
+fn transcript(side: Side, bound: Amount) -> Transcript {
+    let mut transcript = Transcript::new(TRANSCRIPT_LABEL);
+    let label: &[u8] = match side {
+        Side::Min => b"min",
+        Side::Max => b"max",
+    };
+    transcript.append_u64(label, bound);
+    transcript
+}
+
+fn prove(
+    value: Amount,
+    blinding_factor: Scalar,
+    side: Side,
+    bound: Amount,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<RangeProof> {
+    let (proof, _) = RangeProof::prove_single_with_rng(
+        &BulletproofGens::new(RANGE_PROOF_BITS, RANGE_PROOF_PARTIES),
+        &PedersenGens::default(),
+        &mut transcript(side, bound),
+        value,
+        &blinding_factor,
+        RANGE_PROOF_BITS,
+        rng,
+    )
+    .map_err(super::Error::from)?;
+    Ok(proof)
+}
+
+fn verify(proof: &RangeProof, commitment: BlindedAmount, side: Side, bound: Amount) -> Result<()> {
+    proof
+        .verify_single(
+            &BulletproofGens::new(RANGE_PROOF_BITS, RANGE_PROOF_PARTIES),
+            &PedersenGens::default(),
+            &mut transcript(side, bound),
+            &commitment.compress(),
+            RANGE_PROOF_BITS,
+        )
+        .map_err(|_| Error::InvalidAmountRangeProof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proves_bounds_of_the_amount() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let revealed_amount = RevealedAmount::from_amount(250, &mut rng);
+        let blinded_amount = revealed_amount.blinded_amount(&PedersenGens::default());
+
+        for proof in [
+            AmountRangeProof::prove(&revealed_amount, 100.., &mut rng)?,
+            AmountRangeProof::prove(&revealed_amount, ..=250, &mut rng)?,
+            AmountRangeProof::prove(&revealed_amount, 250..251, &mut rng)?,
+            AmountRangeProof::prove(&revealed_amount, 0..=u64::MAX, &mut rng)?,
+        ] {
+            proof.verify(blinded_amount)?;
+        }
+
+        let proof = AmountRangeProof::prove(&revealed_amount, 100..=500, &mut rng)?;
+        assert_eq!((proof.min(), proof.max()), (Some(100), Some(500)));
+
+        // The proof does not hold for another amount.
+        let other_amount = RevealedAmount::from_amount(250, &mut rng);
+        assert_eq!(
+            proof.verify(other_amount.blinded_amount(&PedersenGens::default())),
+            Err(Error::InvalidAmountRangeProof)
+        );
+
+        // Nor for other bounds.
+        let mut tampered = proof.clone();
+        tampered.min = tampered.min.map(|(min, proof)| (min + 1, proof));
+        assert_eq!(
+            tampered.verify(blinded_amount),
+            Err(Error::InvalidAmountRangeProof)
+        );
+        let mut swapped = proof;
+        std::mem::swap(&mut swapped.min, &mut swapped.max);
+        assert_eq!(
+            swapped.verify(blinded_amount),
+            Err(Error::InvalidAmountRangeProof)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_prove_an_amount_out_of_range() {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let revealed_amount = RevealedAmount::from_amount(250, &mut rng);
+
+        for result in [
+            AmountRangeProof::prove(&revealed_amount, 251.., &mut rng),
+            AmountRangeProof::prove(&revealed_amount, ..250, &mut rng),
+            AmountRangeProof::prove(&revealed_amount, 0..0, &mut rng),
+        ] {
+            assert_eq!(result.map(|_| ()), Err(Error::AmountNotInRange));
+        }
+        assert_eq!(
+            AmountRangeProof::prove(&revealed_amount, .., &mut rng).map(|_| ()),
+            Err(Error::UnboundedAmountRange)
+        );
+    }
+}
@@ -16,10 +16,16 @@ pub enum Error {
     InconsistentDbcTransaction,
     #[error("The signature is not valid.")]
     InvalidSignature,
+    #[error("The inputs must either each be signed, or have their signatures aggregated into the one of the tx.")]
+    InconsistentInputSignatures,
     #[error("BulletProofs Error: {0}.")]
     BulletProofs(#[from] bulletproofs::ProofError),
     #[error("The Dbc tx must have at least one input.")]
     MissingTxInputs,
     #[error("Dbc id is not unique across all tx inputs.")]
     DbcIdNotUniqueAcrossInputs,
+    #[error("The tx is of version {0}, which is not supported.")]
+    UnsupportedTxVersion(u16),
+    #[error("Legacy txs are only valid on the mainnet, not on {0}.")]
+    LegacyTxNotOnMainnet(crate::NetworkId),
 }
@@ -10,7 +10,7 @@ use bulletproofs::PedersenGens;
 #[cfg(feature = "serde")]
 use serde::{self, Deserialize, Serialize};
 
-use super::{Error, Result, RevealedAmount};
+use super::{aggregate, Error, Result, RevealedAmount};
 use crate::{BlindedAmount, DbcId, DerivedKey};
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -45,9 +45,27 @@ impl RevealedInput {
         BlindedInput {
             dbc_id: self.dbc_id(),
             blinded_amount: self.blinded_amount(pc_gens),
-            signature: self.derived_key.sign(msg),
+            signature: Some(self.derived_key.sign(msg)),
         }
     }
+
+    /// Sign the tx message for an aggregated signature of the tx.
+    /// Returns the input without a signature, along with the signature to aggregate.
+    pub fn sign_for_aggregation(
+        &self,
+        msg: &[u8],
+        pc_gens: &PedersenGens,
+    ) -> (BlindedInput, Signature) {
+        let input = BlindedInput {
+            dbc_id: self.dbc_id(),
+            blinded_amount: self.blinded_amount(pc_gens),
+            signature: None,
+        };
+        let signature = self
+            .derived_key
+            .sign(&aggregate::input_msg(&input.dbc_id, msg));
+        (input, signature)
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -56,8 +74,9 @@ pub struct BlindedInput {
     pub dbc_id: DbcId,
     pub blinded_amount: BlindedAmount,
     /// This is the signature of the `DerivedKey`
-    /// corresponding to this `dbc_id`
-    pub signature: Signature,
+    /// corresponding to this `dbc_id`.
+    /// It is None when the signature is aggregated into the one of the tx.
+    pub signature: Option<Signature>,
 }
 
 impl BlindedInput {
@@ -65,7 +84,9 @@ impl BlindedInput {
         let mut v: Vec<u8> = Default::default();
         v.extend(self.dbc_id.to_bytes().as_ref());
         v.extend(self.blinded_amount.compress().as_bytes());
-        v.extend(self.signature.to_bytes().as_ref());
+        if let Some(signature) = &self.signature {
+            v.extend(signature.to_bytes().as_ref());
+        }
         v
     }
 
@@ -77,15 +98,28 @@ impl BlindedInput {
     /// and that the bytes passed in, are what the signature of this input was made over,
     /// and that the public key of this input was the signer.
     pub fn verify(&self, msg: &[u8], blinded_amount: BlindedAmount) -> Result<()> {
-        if self.blinded_amount != blinded_amount {
-            return Err(Error::InvalidInputBlindedAmount);
-        }
+        self.verify_blinded_amount(blinded_amount)?;
+        self.verify_signature(msg)
+    }
 
-        // check the signature
-        if !self.dbc_id.verify(&self.signature, msg) {
+    /// Verify that the bytes passed in, are what the signature of this input was made over,
+    /// and that the public key of this input was the signer.
+    pub fn verify_signature(&self, msg: &[u8]) -> Result<()> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or(Error::InconsistentInputSignatures)?;
+        if !self.dbc_id.verify(signature, msg) {
             return Err(Error::InvalidSignature);
         }
+        Ok(())
+    }
 
+    /// Verify if a blinded amount you know of, is the same as the one in the input.
+    pub fn verify_blinded_amount(&self, blinded_amount: BlindedAmount) -> Result<()> {
+        if self.blinded_amount != blinded_amount {
+            return Err(Error::InvalidInputBlindedAmount);
+        }
         Ok(())
     }
 }
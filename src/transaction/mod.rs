@@ -4,13 +4,15 @@
 // This SAFE Network Software is licensed under the BSD-3-Clause license.
 // Please see the LICENSE file for more details.
 
+mod aggregate;
 mod amount;
+mod amount_range_proof;
 mod error;
 mod input;
 mod output;
 
 use crate::rand::{CryptoRng, RngCore};
-use crate::{BlindedAmount, DbcId};
+use crate::{BlindedAmount, DbcId, NetworkId, Signature};
 
 use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
 use curve25519_dalek::ristretto::RistrettoPoint;
@@ -23,6 +25,7 @@ use tiny_keccak::{Hasher, Sha3};
 use serde::{Deserialize, Serialize};
 
 pub use amount::{Amount, RevealedAmount};
+pub use amount_range_proof::AmountRangeProof;
 pub(crate) use error::Error;
 pub use input::{BlindedInput, RevealedInput};
 pub use output::{BlindedOutput, Output, RevealedOutput};
@@ -37,8 +40,19 @@ type Result<T> = std::result::Result<T, Error>;
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct DbcTransaction {
+    /// The version of the tx protocol, which defines how the tx is verified.
+    pub version: u16,
     pub inputs: Vec<BlindedInput>,
     pub outputs: Vec<BlindedOutput>,
+    /// The signatures of all the inputs, aggregated into one.
+    /// It is None when each input carries its own signature.
+    ///
+    /// note: this field, and the optional signature of each input, change the serialized
+    ///       layout of a tx from the one of 19.1.0. Dbcs of 19.1.0 are migrated with
+    ///       `Dbc::from_legacy_hex()`.
+    pub aggregated_signature: Option<Signature>,
+    /// The network that the tx is valid on.
+    pub network: NetworkId,
 }
 
 impl PartialEq for DbcTransaction {
@@ -61,9 +75,37 @@ impl Ord for DbcTransaction {
     }
 }
 
+/// The sets of rules that txs are verified by, each for one or more versions.
+#[derive(Clone, Copy)]
+enum TxRules {
+    /// The range proofs of the outputs are chained on a single transcript.
+    V1,
+    /// Each range proof has a transcript of its own.
+    V2,
+}
+
 impl DbcTransaction {
+    /// The version of the txs signed by this version of the crate.
+    /// Txs of older versions are still verified, as long as they are supported.
+    pub const VERSION: u16 = Self::V2;
+
+    /// The version of the txs of sn_dbc 19.1.0 and before, which commit to neither their
+    /// version nor their network. They are thus only valid on the mainnet.
+    /// Such txs are obtained by migrating the Dbcs of 19.1.0, see `LegacyDbc`.
+    pub const LEGACY_VERSION: u16 = 0;
+
+    /// The first version of txs which commit to their version and network.
+    pub const V1: u16 = 1;
+
+    /// The version of txs where the range proof of each output has a transcript of its own,
+    /// committing to the position and DbcId of the output, instead of being chained on the
+    /// transcript of the proofs before it. The proofs can thus be generated and verified
+    /// independently of each other.
+    pub const V2: u16 = 2;
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut v: Vec<u8> = Default::default();
+        extend_with_version(&mut v, self.version, self.network);
         v.extend("inputs".as_bytes());
         for m in self.inputs.iter() {
             v.extend(&m.to_bytes());
@@ -72,6 +114,10 @@ impl DbcTransaction {
         for o in self.outputs.iter() {
             v.extend(&o.to_bytes());
         }
+        if let Some(signature) = &self.aggregated_signature {
+            v.extend("aggregated_signature".as_bytes());
+            v.extend(signature.to_bytes().as_ref());
+        }
         v.extend("end".as_bytes());
         v
     }
@@ -91,33 +137,56 @@ impl DbcTransaction {
         // All input blinded amounts
         let input_amounts: Vec<BlindedAmount> =
             self.inputs.iter().map(|i| i.blinded_amount).collect();
-        serialize(&dbc_ids, &input_amounts, &self.outputs)
+        serialize(
+            self.version,
+            self.network,
+            &dbc_ids,
+            &input_amounts,
+            &self.outputs,
+        )
     }
 
     /// Verify if the blinded amounts of the inputs, are
     /// the same as the set of blinded amounts you know of.
     /// This also checks that every input has the signature over this very tx,
     /// and that each public key of the inputs was the signer.
+    /// The signatures are either carried by each input, or aggregated into one.
+    ///
+    /// The tx is verified by the rules of its version.
     pub fn verify(&self, blinded_amounts: &[BlindedAmount]) -> Result<()> {
+        let rules = self.rules()?;
+        self.verify_by(rules, blinded_amounts)
+    }
+
+    /// Check that the version of the tx is supported, on the network of the tx.
+    pub(crate) fn verify_version(&self) -> Result<()> {
+        self.rules().map(|_| ())
+    }
+
+    /// The rules that the tx is verified by, given its version.
+    fn rules(&self) -> Result<TxRules> {
+        rules(self.version, self.network)
+    }
+
+    fn verify_by(&self, rules: TxRules, blinded_amounts: &[BlindedAmount]) -> Result<()> {
         // check input sigs
         let msg = self.serialize_tx();
-        for (input, blinded_amount) in self.inputs.iter().zip(blinded_amounts) {
-            input.verify(&msg, *blinded_amount)?
+        let inputs: Vec<_> = self.inputs.iter().zip(blinded_amounts).collect();
+        match &self.aggregated_signature {
+            None => crate::parallel::try_for_each(&inputs, |(input, blinded_amount)| {
+                input.verify(&msg, **blinded_amount)
+            })?,
+            Some(_) => {
+                for (input, blinded_amount) in inputs {
+                    input.verify_blinded_amount(*blinded_amount)?;
+                }
+                self.verify_aggregated_signature(&msg)?;
+            }
         }
 
-        let mut prover_ts = Transcript::new(MERLIN_TRANSCRIPT_LABEL);
-        let bp_gens = RevealedTx::bp_gens();
-
-        for output in self.outputs.iter() {
-            // Verification requires a transcript with identical initial state:
-            output.range_proof.verify_single(
-                &bp_gens,
-                &RevealedTx::pc_gens(),
-                &mut prover_ts,
-                &output.blinded_amount.compress(),
-                RANGE_PROOF_BITS,
-            )?;
-        }
+        self.verify_range_proofs_by(rules)
+            .into_iter()
+            .collect::<Result<()>>()?;
 
         // Verify that the tx has at least one input
         if self.inputs.is_empty() {
@@ -131,19 +200,69 @@ impl DbcTransaction {
             return Err(Error::DbcIdNotUniqueAcrossInputs);
         }
 
-        // Check that the input and output blinded amounts are equal.
-        let input_sum: RistrettoPoint = self
+        self.verify_balance()
+    }
+
+    /// Verify the aggregated signature of the inputs over the given tx message.
+    /// The inputs must then not carry signatures of their own.
+    pub(crate) fn verify_aggregated_signature(&self, msg: &[u8]) -> Result<()> {
+        let aggregated_signature = match &self.aggregated_signature {
+            Some(signature) if self.inputs.iter().all(|i| i.signature.is_none()) => signature,
+            _ => return Err(Error::InconsistentInputSignatures),
+        };
+        let signers: Vec<_> = self
             .inputs
             .iter()
-            .map(|i| i.blinded_amount)
-            .map(RistrettoPoint::from)
-            .sum();
-        let output_sum: RistrettoPoint = self
-            .outputs
-            .iter()
-            .map(BlindedOutput::blinded_amount)
-            .map(RistrettoPoint::from)
-            .sum();
+            .map(|i| (i.dbc_id, aggregate::input_msg(&i.dbc_id, msg)))
+            .collect();
+        if !aggregate::verify(aggregated_signature, &signers) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Verify the range proof of each output, in the order of the outputs.
+    ///
+    /// The range proofs of txs of version 2 are verified independently of each other.
+    /// Those of older txs are chained on a single transcript, so they are verified one
+    /// by one, and a failing proof also fails the proofs after it.
+    pub(crate) fn verify_range_proofs(&self) -> Vec<Result<()>> {
+        match self.rules() {
+            Ok(rules) => self.verify_range_proofs_by(rules),
+            Err(error) => self.outputs.iter().map(|_| Err(error.clone())).collect(),
+        }
+    }
+
+    fn verify_range_proofs_by(&self, rules: TxRules) -> Vec<Result<()>> {
+        let bp_gens = RevealedTx::bp_gens();
+        match rules {
+            TxRules::V1 => {
+                let mut prover_ts = range_proof_transcript(self.version, self.network);
+                self.outputs
+                    .iter()
+                    .map(|output| verify_range_proof(output, &mut prover_ts, &bp_gens))
+                    .collect()
+            }
+            TxRules::V2 => {
+                let outputs: Vec<_> = self.outputs.iter().enumerate().collect();
+                crate::parallel::map(&outputs, |(index, output)| {
+                    let mut prover_ts = output_range_proof_transcript(
+                        self.version,
+                        self.network,
+                        *index,
+                        output.dbc_id(),
+                    );
+                    verify_range_proof(output, &mut prover_ts, &bp_gens)
+                })
+            }
+        }
+    }
+
+    /// Check that the input and output blinded amounts are equal.
+    pub(crate) fn verify_balance(&self) -> Result<()> {
+        let input_sum: RistrettoPoint = self.inputs.iter().map(|i| i.blinded_amount).sum();
+        let output_sum: RistrettoPoint =
+            self.outputs.iter().map(BlindedOutput::blinded_amount).sum();
 
         if input_sum != output_sum {
             Err(Error::InconsistentDbcTransaction)
@@ -171,12 +290,40 @@ impl InputHistory {
 pub struct RevealedTx {
     pub inputs: Vec<InputHistory>,
     pub outputs: Vec<Output>,
+    /// The network that the tx is signed for.
+    pub network: NetworkId,
 }
 
 impl RevealedTx {
     pub fn sign(
         &self,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<(DbcTransaction, Vec<RevealedOutput>)> {
+        self.sign_inputs(rng, false)
+    }
+
+    /// Like `sign()`, but the signatures of the inputs are aggregated into a single
+    /// signature of the tx, which is smaller, and faster to verify for many inputs.
+    pub fn sign_aggregated(
+        &self,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<(DbcTransaction, Vec<RevealedOutput>)> {
+        self.sign_inputs(rng, true)
+    }
+
+    fn sign_inputs(
+        &self,
+        rng: impl RngCore + CryptoRng,
+        aggregate_signatures: bool,
+    ) -> Result<(DbcTransaction, Vec<RevealedOutput>)> {
+        self.sign_inputs_of_version(DbcTransaction::VERSION, rng, aggregate_signatures)
+    }
+
+    fn sign_inputs_of_version(
+        &self,
+        version: u16,
         mut rng: impl RngCore + CryptoRng,
+        aggregate_signatures: bool,
     ) -> Result<(DbcTransaction, Vec<RevealedOutput>)> {
         // We need to gather a bunch of things for our message to sign.
         //   All public keys in all inputs
@@ -194,23 +341,44 @@ impl RevealedTx {
         // Adjust the outputs so that summed blinding factors of inputs and outputs are equal.
         let adjusted_revealed_outputs =
             self.adjusted_revealed_outputs(&revealed_input_amounts, &mut rng);
-        let blinded_outputs = self.blinded_outputs(&adjusted_revealed_outputs, &mut rng)?;
+        let blinded_outputs =
+            self.blinded_outputs(version, &adjusted_revealed_outputs, &mut rng)?;
 
         // Generate message to sign.
         // note: must match message generated by DbcTransaction::verify()
-        let msg = serialize(&self.input_ids(), &input_amounts, &blinded_outputs);
+        let msg = serialize(
+            version,
+            self.network,
+            &self.input_ids(),
+            &input_amounts,
+            &blinded_outputs,
+        );
 
         // We create a signature for each input
-        let blinded_inputs: Vec<BlindedInput> = self
-            .inputs
-            .iter()
-            .map(|input_history| input_history.input.sign(&msg, &Self::pc_gens()))
-            .collect();
+        let (blinded_inputs, aggregated_signature) = if aggregate_signatures {
+            let (blinded_inputs, signatures): (Vec<BlindedInput>, Vec<Signature>) =
+                crate::parallel::map(&self.inputs, |input_history| {
+                    input_history
+                        .input
+                        .sign_for_aggregation(&msg, &Self::pc_gens())
+                })
+                .into_iter()
+                .unzip();
+            (blinded_inputs, Some(aggregate::aggregate(&signatures)?))
+        } else {
+            let blinded_inputs = crate::parallel::map(&self.inputs, |input_history| {
+                input_history.input.sign(&msg, &Self::pc_gens())
+            });
+            (blinded_inputs, None)
+        };
 
         Ok((
             DbcTransaction {
+                version,
                 inputs: blinded_inputs,
                 outputs: blinded_outputs,
+                aggregated_signature,
+                network: self.network,
             },
             adjusted_revealed_outputs,
         ))
@@ -300,50 +468,158 @@ impl RevealedTx {
         revealed_outputs
     }
 
+    // note: the range proofs of txs of version 2 are independent of each other, so they
+    //       are generated in parallel with the `parallel` feature. Each proof gets its own
+    //       rng, seeded from the given one in the order of the outputs, so that the proofs
+    //       are the same as those of the sequential path.
     fn blinded_outputs(
         &self,
+        version: u16,
         revealed_outputs: &[RevealedOutput],
         mut rng: impl RngCore + CryptoRng,
     ) -> Result<Vec<BlindedOutput>> {
-        let mut prover_ts = Transcript::new(MERLIN_TRANSCRIPT_LABEL);
-
         let bp_gens = Self::bp_gens();
 
-        revealed_outputs
-            .iter()
-            .map(|c| {
-                let (range_proof, compressed_blinded_amount) = RangeProof::prove_single_with_rng(
-                    &bp_gens,
-                    &Self::pc_gens(),
-                    &mut prover_ts,
-                    c.revealed_amount.value,
-                    &c.revealed_amount.blinding_factor,
-                    RANGE_PROOF_BITS,
-                    &mut rng,
-                )?;
-                let blinded_amount = compressed_blinded_amount
-                    .decompress()
-                    .ok_or(Error::FailedToDecompressBlindedAmount)?;
-
-                Ok(BlindedOutput {
-                    dbc_id: c.dbc_id,
-                    range_proof,
-                    blinded_amount,
+        match rules(version, self.network)? {
+            TxRules::V1 => {
+                let mut prover_ts = range_proof_transcript(version, self.network);
+                revealed_outputs
+                    .iter()
+                    .map(|output| prove_range(output, &mut prover_ts, &bp_gens, &mut rng))
+                    .collect()
+            }
+            TxRules::V2 => {
+                let outputs: Vec<_> = revealed_outputs
+                    .iter()
+                    .enumerate()
+                    .map(|(index, output)| {
+                        let mut seed = [0u8; 32];
+                        rng.fill_bytes(&mut seed);
+                        (index, output, seed)
+                    })
+                    .collect();
+                crate::parallel::map(&outputs, |(index, output, seed)| {
+                    let mut prover_ts = output_range_proof_transcript(
+                        version,
+                        self.network,
+                        *index,
+                        &output.dbc_id,
+                    );
+                    let mut rng = crate::rng::from_seed(*seed);
+                    prove_range(output, &mut prover_ts, &bp_gens, &mut rng)
                 })
-            })
-            .collect::<Result<Vec<_>>>()
+                .into_iter()
+                .collect()
+            }
+        }
+    }
+}
+
+/// The rules that the txs of the given version are verified by, on the given network.
+fn rules(version: u16, network: NetworkId) -> Result<TxRules> {
+    match version {
+        DbcTransaction::LEGACY_VERSION if network != NetworkId::Mainnet => {
+            Err(Error::LegacyTxNotOnMainnet(network))
+        }
+        // Legacy txs only differ from version 1 in what their signatures
+        // and range proofs commit to.
+        DbcTransaction::LEGACY_VERSION | DbcTransaction::V1 => Ok(TxRules::V1),
+        DbcTransaction::V2 => Ok(TxRules::V2),
+        version => Err(Error::UnsupportedTxVersion(version)),
+    }
+}
+
+fn prove_range(
+    output: &RevealedOutput,
+    prover_ts: &mut Transcript,
+    bp_gens: &BulletproofGens,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<BlindedOutput> {
+    let (range_proof, compressed_blinded_amount) = RangeProof::prove_single_with_rng(
+        bp_gens,
+        &RevealedTx::pc_gens(),
+        prover_ts,
+        output.revealed_amount.value,
+        &output.revealed_amount.blinding_factor,
+        RANGE_PROOF_BITS,
+        rng,
+    )?;
+    let blinded_amount = compressed_blinded_amount
+        .decompress()
+        .ok_or(Error::FailedToDecompressBlindedAmount)?;
+
+    Ok(BlindedOutput {
+        dbc_id: output.dbc_id,
+        range_proof,
+        blinded_amount,
+    })
+}
+
+fn verify_range_proof(
+    output: &BlindedOutput,
+    prover_ts: &mut Transcript,
+    bp_gens: &BulletproofGens,
+) -> Result<()> {
+    // Verification requires a transcript with identical initial state:
+    output
+        .range_proof
+        .verify_single(
+            bp_gens,
+            &RevealedTx::pc_gens(),
+            prover_ts,
+            &output.blinded_amount.compress(),
+            RANGE_PROOF_BITS,
+        )
+        .map_err(Error::from)
+}
+
+/// The transcript that the range proofs of a tx on the network are chained on.
+/// The one of legacy txs does not commit to a network.
+fn range_proof_transcript(version: u16, network: NetworkId) -> Transcript {
+    let mut transcript = Transcript::new(MERLIN_TRANSCRIPT_LABEL);
+    if version != DbcTransaction::LEGACY_VERSION {
+        transcript.append_message(b"network", network.name().as_bytes());
+    }
+    transcript
+}
+
+/// The transcript of the range proof of the output at the given index of a tx,
+/// see `DbcTransaction::V2`.
+fn output_range_proof_transcript(
+    version: u16,
+    network: NetworkId,
+    index: usize,
+    dbc_id: &DbcId,
+) -> Transcript {
+    let mut transcript = range_proof_transcript(version, network);
+    transcript.append_u64(b"output", index as u64);
+    transcript.append_message(b"dbc_id", &dbc_id.to_bytes());
+    transcript
+}
+
+/// Commit to the version and network of a tx.
+/// Legacy txs commit to neither, see `DbcTransaction::LEGACY_VERSION`.
+fn extend_with_version(bytes: &mut Vec<u8>, version: u16, network: NetworkId) {
+    if version != DbcTransaction::LEGACY_VERSION {
+        bytes.extend("version".as_bytes());
+        bytes.extend(version.to_le_bytes());
+        bytes.extend("network".as_bytes());
+        bytes.extend(network.name().as_bytes());
     }
 }
 
 // note: used by both RevealedTx::sign and DbcTransaction::verify()
 //       which must match.
 fn serialize(
+    version: u16,
+    network: NetworkId,
     dbc_ids: &[DbcId],
     input_amounts: &[BlindedAmount],
     blinded_outputs: &[BlindedOutput],
 ) -> Vec<u8> {
     // Generate message to sign.
     let mut msg: Vec<u8> = Default::default();
+    extend_with_version(&mut msg, version, network);
     msg.extend("dbc_ids".as_bytes());
     for id in dbc_ids.iter() {
         msg.extend(id.to_bytes().as_ref());
@@ -370,7 +646,7 @@ mod tests {
 
     #[test]
     fn test_input_sign() {
-        let mut rng = OsRng::default();
+        let mut rng = OsRng;
         let pc_gens = PedersenGens::default();
         let tx_amount = 3;
 
@@ -385,8 +661,11 @@ mod tests {
         let input_history = InputHistory {
             input: revealed_input,
             input_src_tx: DbcTransaction {
+                version: DbcTransaction::VERSION,
                 inputs: vec![],
                 outputs: vec![],
+                aggregated_signature: None,
+                network: NetworkId::Mainnet,
             },
         };
 
@@ -410,6 +689,7 @@ mod tests {
                 dbc_id: DerivedKey::new(SecretKey::random()).dbc_id(),
                 amount: tx_amount,
             }],
+            network: NetworkId::Mainnet,
         };
 
         let (signed_tx, _revealed_output_amounts) =
@@ -424,4 +704,182 @@ mod tests {
 
         assert!(signed_tx.verify(&blinded_amounts).is_ok());
     }
+
+    #[test]
+    fn a_tx_is_only_valid_on_its_network() {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let revealed_input = RevealedInput::new(
+            DerivedKey::new(SecretKey::random()),
+            RevealedAmount::from_amount(10, &mut rng),
+        );
+        let blinded_amounts = [revealed_input.blinded_amount(&pc_gens)];
+        let revealed_tx = RevealedTx {
+            inputs: vec![InputHistory {
+                input: revealed_input,
+                input_src_tx: DbcTransaction {
+                    version: DbcTransaction::VERSION,
+                    inputs: vec![],
+                    outputs: vec![],
+                    aggregated_signature: None,
+                    network: NetworkId::Testnet,
+                },
+            }],
+            outputs: (0..2)
+                .map(|_| Output {
+                    dbc_id: DerivedKey::new(SecretKey::random()).dbc_id(),
+                    amount: 5,
+                })
+                .collect(),
+            network: NetworkId::Testnet,
+        };
+        let (tx, _) = revealed_tx.sign(&mut rng).expect("Failed to sign tx");
+        assert!(tx.verify(&blinded_amounts).is_ok());
+
+        // Replayed on another network, neither the signatures nor the range proofs are valid.
+        let mut replayed_tx = tx.clone();
+        replayed_tx.network = NetworkId::Mainnet;
+        assert_ne!(replayed_tx.hash(), tx.hash());
+        assert_eq!(
+            replayed_tx.verify(&blinded_amounts),
+            Err(Error::InvalidSignature)
+        );
+        assert!(replayed_tx
+            .verify_range_proofs()
+            .iter()
+            .all(|result| result.is_err()));
+    }
+
+    fn revealed_tx_of_three_outputs(rng: &mut (impl RngCore + CryptoRng)) -> RevealedTx {
+        let derived_key = DerivedKey::new(SecretKey::random());
+        RevealedTx {
+            inputs: vec![InputHistory {
+                input: RevealedInput::new(derived_key, RevealedAmount::from_amount(30, rng)),
+                input_src_tx: DbcTransaction {
+                    version: DbcTransaction::VERSION,
+                    inputs: vec![],
+                    outputs: vec![],
+                    aggregated_signature: None,
+                    network: NetworkId::Mainnet,
+                },
+            }],
+            outputs: (0..3)
+                .map(|_| Output {
+                    dbc_id: DerivedKey::new(SecretKey::random()).dbc_id(),
+                    amount: 10,
+                })
+                .collect(),
+            network: NetworkId::Mainnet,
+        }
+    }
+
+    #[test]
+    fn range_proofs_of_v2_txs_are_independent() {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (tx, _) = revealed_tx_of_three_outputs(&mut rng)
+            .sign(&mut rng)
+            .expect("Failed to sign transaction");
+        assert_eq!(tx.version, DbcTransaction::V2);
+        assert!(tx.verify_range_proofs().iter().all(|result| result.is_ok()));
+
+        // A bad proof only fails its own output.
+        let mut tampered_tx = tx.clone();
+        tampered_tx.outputs[0].range_proof = tx.outputs[1].range_proof.clone();
+        let results = tampered_tx.verify_range_proofs();
+        assert!(results[0].is_err() && results[1].is_ok() && results[2].is_ok());
+
+        // A proof is bound to the position of its output.
+        let mut reordered_tx = tx;
+        reordered_tx.outputs.swap(1, 2);
+        let results = reordered_tx.verify_range_proofs();
+        assert!(results[0].is_ok() && results[1].is_err() && results[2].is_err());
+    }
+
+    #[test]
+    fn range_proofs_of_v1_txs_are_chained() {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let revealed_tx = revealed_tx_of_three_outputs(&mut rng);
+        let blinded_amounts: Vec<BlindedAmount> = revealed_tx
+            .inputs
+            .iter()
+            .map(|i| i.input.blinded_amount(&PedersenGens::default()))
+            .collect();
+        let (tx, _) = revealed_tx
+            .sign_inputs_of_version(DbcTransaction::V1, &mut rng, false)
+            .expect("Failed to sign transaction");
+        assert_eq!(tx.version, DbcTransaction::V1);
+        assert!(tx.verify(&blinded_amounts).is_ok());
+
+        // A bad proof also fails the proofs chained after it.
+        let mut tampered_tx = tx.clone();
+        tampered_tx.outputs[1].range_proof = tx.outputs[2].range_proof.clone();
+        let results = tampered_tx.verify_range_proofs();
+        assert!(results[0].is_ok() && results[1].is_err() && results[2].is_err());
+    }
+
+    #[test]
+    fn aggregated_signature_replaces_the_input_signatures() {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let pc_gens = PedersenGens::default();
+        let revealed_tx = RevealedTx {
+            inputs: (0..3)
+                .map(|_| InputHistory {
+                    input: RevealedInput::new(
+                        DerivedKey::new(SecretKey::random()),
+                        RevealedAmount::from_amount(10, &mut rng),
+                    ),
+                    input_src_tx: DbcTransaction {
+                        version: DbcTransaction::VERSION,
+                        inputs: vec![],
+                        outputs: vec![],
+                        aggregated_signature: None,
+                        network: NetworkId::Mainnet,
+                    },
+                })
+                .collect(),
+            outputs: vec![Output {
+                dbc_id: DerivedKey::new(SecretKey::random()).dbc_id(),
+                amount: 30,
+            }],
+            network: NetworkId::Mainnet,
+        };
+        let blinded_amounts: Vec<BlindedAmount> = revealed_tx
+            .inputs
+            .iter()
+            .map(|i| i.input.blinded_amount(&pc_gens))
+            .collect();
+
+        let (tx, _) = revealed_tx.sign(&mut rng).expect("Failed to sign tx");
+        let (aggregated_tx, _) = revealed_tx
+            .sign_aggregated(&mut rng)
+            .expect("Failed to sign tx");
+
+        // Both forms are accepted.
+        assert!(tx.verify(&blinded_amounts).is_ok());
+        assert!(aggregated_tx.verify(&blinded_amounts).is_ok());
+        assert!(aggregated_tx.inputs.iter().all(|i| i.signature.is_none()));
+        assert!(aggregated_tx.to_bytes().len() < tx.to_bytes().len());
+
+        // The signature of another tx is not valid for this one.
+        let mut invalid_tx = aggregated_tx.clone();
+        invalid_tx.aggregated_signature = tx.inputs[0].signature.clone();
+        assert_eq!(
+            invalid_tx.verify(&blinded_amounts),
+            Err(Error::InvalidSignature)
+        );
+
+        // The forms cannot be mixed.
+        let mut mixed_tx = aggregated_tx.clone();
+        mixed_tx.inputs[0].signature = tx.inputs[0].signature.clone();
+        assert_eq!(
+            mixed_tx.verify(&blinded_amounts),
+            Err(Error::InconsistentInputSignatures)
+        );
+        let mut unsigned_tx = tx;
+        unsigned_tx.inputs[0].signature = None;
+        assert_eq!(
+            unsigned_tx.verify(&blinded_amounts),
+            Err(Error::InconsistentInputSignatures)
+        );
+    }
 }
@@ -51,6 +51,20 @@ pub struct BlindedOutput {
 }
 
 impl BlindedOutput {
+    /// Reassemble an output from its parts, eg. when decoding it.
+    /// The range proof is checked when the tx is verified.
+    pub(crate) fn from_parts(
+        dbc_id: DbcId,
+        range_proof: RangeProof,
+        blinded_amount: BlindedAmount,
+    ) -> Self {
+        Self {
+            dbc_id,
+            range_proof,
+            blinded_amount,
+        }
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut v: Vec<u8> = Default::default();
         v.extend(self.dbc_id.to_bytes().as_ref());
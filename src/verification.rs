@@ -8,10 +8,13 @@
 
 use crate::{
     transaction::{self, DbcTransaction},
-    BlindedAmount, DbcId, Error, Result, SignedSpend,
+    BlindedAmount, DbcId, Error, Hash, Result, SignedSpend, VerificationReport,
 };
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+/// The number of verified txs kept by a VerifiedTxCache by default.
+pub const DEFAULT_VERIFIED_TX_CACHE_CAPACITY: usize = 1024;
 
 // Here we are putting transaction verification logic that is beyond
 // what DbcTransaction::verify() provides.
@@ -31,6 +34,25 @@ impl TransactionVerifier {
     pub fn verify(
         spent_tx: &DbcTransaction,
         signed_spends: &BTreeSet<SignedSpend>,
+    ) -> Result<(), Error> {
+        Self::verify_with_cache(spent_tx, signed_spends, &mut VerifiedTxCache::new(0))
+    }
+
+    /// Runs every check of `verify()`, instead of returning on the first failure,
+    /// and reports the outcome of each.
+    pub fn verify_report(
+        spent_tx: &DbcTransaction,
+        signed_spends: &BTreeSet<SignedSpend>,
+    ) -> VerificationReport {
+        VerificationReport::of_tx(spent_tx, signed_spends)
+    }
+
+    /// Verifies a transaction including signed spends, like `verify()`, but does not
+    /// verify the tx itself again, if it has already been verified with the same input amounts.
+    pub fn verify_with_cache(
+        spent_tx: &DbcTransaction,
+        signed_spends: &BTreeSet<SignedSpend>,
+        cache: &mut VerifiedTxCache,
     ) -> Result<(), Error> {
         if signed_spends.is_empty() {
             return Err(transaction::Error::MissingTxInputs)?;
@@ -43,11 +65,13 @@ impl TransactionVerifier {
             });
         }
 
+        spent_tx.verify_version()?;
+
         let spent_tx_hash = spent_tx.hash();
 
         // Verify that each pubkey is unique in this transaction.
         let unique_dbc_ids: BTreeSet<DbcId> =
-            spent_tx.outputs.iter().map(|o| (*o.dbc_id())).collect();
+            spent_tx.outputs.iter().map(|o| *o.dbc_id()).collect();
         if unique_dbc_ids.len() != spent_tx.outputs.len() {
             return Err(Error::DbcIdNotUniqueAcrossOutputs);
         }
@@ -63,11 +87,23 @@ impl TransactionVerifier {
             }
         }
 
-        // Verify that each signed spend is valid
+        // Verify that each input was created on the network of this tx.
         for signed_spend in signed_spends.iter() {
-            signed_spend.verify(spent_tx_hash)?;
+            let found = signed_spend.spend.dbc_creation_tx.network;
+            if found != spent_tx.network {
+                return Err(Error::NetworkMismatch {
+                    expected: spent_tx.network,
+                    found,
+                });
+            }
         }
 
+        // Verify that each signed spend is valid
+        let signed_spends_vec: Vec<&SignedSpend> = signed_spends.iter().collect();
+        crate::parallel::try_for_each(&signed_spends_vec, |signed_spend| {
+            signed_spend.verify(spent_tx_hash)
+        })?;
+
         // We must get the signed spends into the same order as inputs
         // so that resulting blinded amounts will be in the right order.
         // Note: we could use itertools crate to sort in one loop.
@@ -91,10 +127,95 @@ impl TransactionVerifier {
             .map(|s| *s.blinded_amount())
             .collect();
 
-        spent_tx.verify(&blinded_amounts)?;
+        cache.verify(spent_tx, &blinded_amounts)?;
+
+        Ok(())
+    }
+}
+
+/// A bounded cache of txs that have been verified.
+///
+/// Verifying the range proofs of a tx is expensive, and a tx is typically verified once
+/// per input, as each of its SignedSpends is logged. With this cache, it is verified once.
+///
+/// A tx is only valid given the blinded amounts of its inputs, so entries are keyed by the
+/// hash of the tx together with those amounts. A tx verified against some amounts will thus
+/// be verified again if checked against other amounts.
+///
+/// When full, the oldest entry is evicted.
+#[derive(Debug, Clone)]
+pub struct VerifiedTxCache {
+    capacity: usize,
+    keys: HashSet<Hash>,
+    order: VecDeque<Hash>,
+}
+
+impl Default for VerifiedTxCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_VERIFIED_TX_CACHE_CAPACITY)
+    }
+}
+
+impl VerifiedTxCache {
+    /// Create a cache holding at most `capacity` verified txs.
+    /// A capacity of zero disables the cache.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            keys: Default::default(),
+            order: Default::default(),
+        }
+    }
+
+    /// The key of a tx verified against the given input blinded amounts.
+    pub fn key(tx: &DbcTransaction, blinded_amounts: &[BlindedAmount]) -> Hash {
+        let mut bytes: Vec<u8> = Default::default();
+        bytes.extend(tx.hash().as_ref());
+        for amount in blinded_amounts {
+            bytes.extend(amount.compress().as_bytes());
+        }
+        Hash::hash(&bytes)
+    }
 
+    /// Verify the tx against the given input blinded amounts (see `DbcTransaction::verify()`),
+    /// unless that has already been done.
+    pub fn verify(&mut self, tx: &DbcTransaction, blinded_amounts: &[BlindedAmount]) -> Result<()> {
+        let key = Self::key(tx, blinded_amounts);
+        if self.keys.contains(&key) {
+            return Ok(());
+        }
+        tx.verify(blinded_amounts)?;
+        self.insert(key);
         Ok(())
     }
+
+    /// Returns true if the tx has been verified against the given input blinded amounts.
+    pub fn contains(&self, tx: &DbcTransaction, blinded_amounts: &[BlindedAmount]) -> bool {
+        self.keys.contains(&Self::key(tx, blinded_amounts))
+    }
+
+    /// The number of verified txs in the cache.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    fn insert(&mut self, key: Hash) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        if self.keys.insert(key) {
+            self.order.push_back(key);
+        }
+    }
 }
 
 /// Get the blinded amounts for the transaction.
@@ -147,3 +268,88 @@ pub fn get_blinded_amounts_from_transaction(
 
     Ok(tx_keys_and_blinded_amounts)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verified_tx_cache_is_keyed_by_input_amounts() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, _) = crate::dbc::tests::generate_dbc_and_its_main_key(100, &mut rng)?;
+        let tx = &dbc.src_tx;
+        let blinded_amounts: Vec<BlindedAmount> =
+            tx.inputs.iter().map(|i| i.blinded_amount).collect();
+
+        let mut cache = VerifiedTxCache::new(1);
+        cache.verify(tx, &blinded_amounts)?;
+        assert!(cache.contains(tx, &blinded_amounts));
+
+        // The same tx checked against other amounts is verified again, and fails.
+        let other_amounts = vec![dbc.blinded_amount()?];
+        assert!(cache.verify(tx, &other_amounts).is_err());
+        assert!(!cache.contains(tx, &other_amounts));
+        assert_eq!(cache.len(), 1);
+
+        // The oldest entry is evicted when full.
+        cache.verify(
+            &dbc.signed_spends.first().unwrap().spend.dbc_creation_tx,
+            &[crate::mock::SpentbookNode::default().genesis.1],
+        )?;
+        assert_eq!(cache.len(), 1);
+        assert!(!cache.contains(tx, &blinded_amounts));
+
+        // A cache without capacity never holds anything.
+        let mut cache = VerifiedTxCache::new(0);
+        cache.verify(tx, &blinded_amounts)?;
+        assert!(cache.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, main_key) =
+            crate::dbc::tests::generate_dbc_and_its_main_key(100, &mut rng)?;
+        let derived_key = dbc.derived_key(&main_key)?;
+        let dbc_builder = crate::TransactionBuilder::default()
+            .add_input_dbc(&dbc, &derived_key)?
+            .add_output(
+                crate::Token::from_nano(100),
+                main_key.random_dbc_id_src(&mut rng),
+            )
+            .build(Hash::default(), &mut rng)?;
+        let spent_tx = &dbc_builder.spent_tx;
+        TransactionVerifier::verify(spent_tx, &dbc_builder.signed_spends)?;
+
+        // A tx of a future version is not verified by the rules of the current one.
+        let mut future_tx = spent_tx.clone();
+        future_tx.version = DbcTransaction::VERSION + 1;
+        assert_eq!(
+            TransactionVerifier::verify(&future_tx, &dbc_builder.signed_spends),
+            Err(transaction::Error::UnsupportedTxVersion(future_tx.version).into())
+        );
+        assert_eq!(
+            future_tx.verify(&[dbc.blinded_amount()?]),
+            Err(transaction::Error::UnsupportedTxVersion(future_tx.version))
+        );
+
+        // Nor is a spend of a future version, even if signed by the spent Dbc.
+        let mut spend = dbc_builder.signed_spends.first().unwrap().spend.clone();
+        spend.version = crate::Spend::VERSION + 1;
+        let signed_spend = SignedSpend {
+            derived_key_sig: derived_key.sign(&spend.to_bytes()),
+            spend,
+        };
+        assert_eq!(
+            TransactionVerifier::verify(spent_tx, &BTreeSet::from([signed_spend])),
+            Err(Error::UnsupportedSpendVersion {
+                dbc_id: dbc.id(),
+                version: crate::Spend::VERSION + 1,
+            })
+        );
+
+        Ok(())
+    }
+}
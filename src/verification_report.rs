@@ -0,0 +1,385 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    transaction::{self, DbcTransaction},
+    Dbc, DbcId, Error, MainKey, Result, SignedSpend,
+};
+
+use std::{collections::BTreeSet, fmt};
+
+/// A check run when verifying a tx or a Dbc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Check {
+    /// The tx has at least one input, and there is at least one SignedSpend.
+    InputCount,
+    /// There is one SignedSpend per input.
+    SpendCount,
+    /// The tx is of a version that is supported.
+    TxVersion,
+    /// The DbcId of an input is not used by another input.
+    InputUniqueness,
+    /// The DbcId of an output is not used by another output.
+    OutputUniqueness,
+    /// A SignedSpend is of one of the inputs.
+    SpendInputMatch,
+    /// The input of a SignedSpend was created on the network of the tx.
+    InputNetwork,
+    /// A SignedSpend is of this tx, and is signed by the DerivedKey of the spent Dbc.
+    SpendSignature,
+    /// The blinded amount of an input is the one of its SignedSpend.
+    InputAmount,
+    /// An input signed the tx, or all inputs did by the aggregated signature.
+    InputSignature,
+    /// The range proof of an output is valid.
+    RangeProof,
+    /// The sum of the blinded amounts of the inputs is the one of the outputs.
+    Balance,
+    /// A SignedSpend has the same reason as the other SignedSpends of the Dbc.
+    ReasonConsistency,
+    /// The ciphers of the Dbc decrypt to the DbcId of one of the outputs of its tx.
+    CipherOutputMatch,
+    /// The amount in the ciphers of the Dbc is the blinded amount of its output.
+    AmountMatch,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::InputCount => "input count",
+            Self::SpendCount => "spend count",
+            Self::TxVersion => "tx version",
+            Self::InputUniqueness => "input uniqueness",
+            Self::OutputUniqueness => "output uniqueness",
+            Self::SpendInputMatch => "spend input match",
+            Self::InputNetwork => "input network",
+            Self::SpendSignature => "spend signature",
+            Self::InputAmount => "input amount",
+            Self::InputSignature => "input signature",
+            Self::RangeProof => "range proof",
+            Self::Balance => "balance",
+            Self::ReasonConsistency => "reason consistency",
+            Self::CipherOutputMatch => "cipher output match",
+            Self::AmountMatch => "amount match",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What a check was run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckSubject {
+    /// The tx as a whole.
+    Tx,
+    /// The input, output, SignedSpend or Dbc with the given DbcId.
+    DbcId(DbcId),
+    /// The output at the given index of the tx.
+    Output(usize),
+}
+
+impl fmt::Display for CheckSubject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tx => write!(f, "tx"),
+            Self::DbcId(dbc_id) => write!(f, "{}", hex::encode(dbc_id.to_bytes())),
+            Self::Output(index) => write!(f, "output {index}"),
+        }
+    }
+}
+
+/// The outcome of one check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    pub check: Check,
+    pub subject: CheckSubject,
+    /// The error the check failed with, if any.
+    pub result: Result<()>,
+}
+
+impl CheckResult {
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// The outcome of every check run when verifying a tx or a Dbc.
+///
+/// Unlike `TransactionVerifier::verify()` and `Dbc::verify()`, which stop at the
+/// first problem, the report holds the result of every check, so that all the
+/// problems of a rejected Dbc can be seen at once.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerificationReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl VerificationReport {
+    /// True if every check passed.
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(CheckResult::passed)
+    }
+
+    /// The checks that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|c| !c.passed())
+    }
+
+    /// The error of the first failed check, if any.
+    pub fn result(&self) -> Result<()> {
+        match self.failures().next() {
+            Some(failure) => failure.result.clone(),
+            None => Ok(()),
+        }
+    }
+
+    fn push(&mut self, check: Check, subject: CheckSubject, result: Result<()>) {
+        self.checks.push(CheckResult {
+            check,
+            subject,
+            result,
+        });
+    }
+
+    /// Run every check of `TransactionVerifier::verify()`.
+    pub(crate) fn of_tx(spent_tx: &DbcTransaction, signed_spends: &BTreeSet<SignedSpend>) -> Self {
+        let mut report = Self::default();
+        let spent_tx_hash = spent_tx.hash();
+
+        report.push(
+            Check::InputCount,
+            CheckSubject::Tx,
+            if spent_tx.inputs.is_empty() || signed_spends.is_empty() {
+                Err(transaction::Error::MissingTxInputs.into())
+            } else {
+                Ok(())
+            },
+        );
+        report.push(
+            Check::SpendCount,
+            CheckSubject::Tx,
+            if signed_spends.len() != spent_tx.inputs.len() {
+                Err(Error::SignedSpendInputLenMismatch {
+                    current: signed_spends.len(),
+                    expected: spent_tx.inputs.len(),
+                })
+            } else {
+                Ok(())
+            },
+        );
+
+        report.push(
+            Check::TxVersion,
+            CheckSubject::Tx,
+            spent_tx.verify_version().map_err(Error::from),
+        );
+
+        let input_ids: Vec<DbcId> = spent_tx.inputs.iter().map(|i| i.dbc_id).collect();
+        for dbc_id in input_ids.iter() {
+            report.push(
+                Check::InputUniqueness,
+                CheckSubject::DbcId(*dbc_id),
+                if input_ids.iter().filter(|id| *id == dbc_id).count() > 1 {
+                    Err(transaction::Error::DbcIdNotUniqueAcrossInputs.into())
+                } else {
+                    Ok(())
+                },
+            );
+        }
+        let output_ids: Vec<DbcId> = spent_tx.outputs.iter().map(|o| *o.dbc_id()).collect();
+        for dbc_id in output_ids.iter() {
+            report.push(
+                Check::OutputUniqueness,
+                CheckSubject::DbcId(*dbc_id),
+                if output_ids.iter().filter(|id| *id == dbc_id).count() > 1 {
+                    Err(Error::DbcIdNotUniqueAcrossOutputs)
+                } else {
+                    Ok(())
+                },
+            );
+        }
+
+        for signed_spend in signed_spends.iter() {
+            let subject = CheckSubject::DbcId(*signed_spend.dbc_id());
+            report.push(
+                Check::SpendInputMatch,
+                subject,
+                if input_ids.contains(signed_spend.dbc_id()) {
+                    Ok(())
+                } else {
+                    Err(Error::SignedSpendInputIdMismatch)
+                },
+            );
+            let found = signed_spend.spend.dbc_creation_tx.network;
+            report.push(
+                Check::InputNetwork,
+                subject,
+                if found != spent_tx.network {
+                    Err(Error::NetworkMismatch {
+                        expected: spent_tx.network,
+                        found,
+                    })
+                } else {
+                    Ok(())
+                },
+            );
+            report.push(
+                Check::SpendSignature,
+                subject,
+                signed_spend.verify(spent_tx_hash),
+            );
+        }
+
+        let msg = spent_tx.serialize_tx();
+        for input in spent_tx.inputs.iter() {
+            let subject = CheckSubject::DbcId(input.dbc_id);
+            report.push(
+                Check::InputAmount,
+                subject,
+                match signed_spends.iter().find(|s| s.dbc_id() == &input.dbc_id) {
+                    Some(signed_spend) => input
+                        .verify_blinded_amount(*signed_spend.blinded_amount())
+                        .map_err(Error::from),
+                    None => Err(Error::MissingAmountForDbcId(input.dbc_id)),
+                },
+            );
+            if spent_tx.aggregated_signature.is_none() {
+                report.push(
+                    Check::InputSignature,
+                    subject,
+                    input.verify_signature(&msg).map_err(Error::from),
+                );
+            }
+        }
+        if spent_tx.aggregated_signature.is_some() {
+            report.push(
+                Check::InputSignature,
+                CheckSubject::Tx,
+                spent_tx
+                    .verify_aggregated_signature(&msg)
+                    .map_err(Error::from),
+            );
+        }
+
+        for (index, result) in spent_tx.verify_range_proofs().into_iter().enumerate() {
+            report.push(
+                Check::RangeProof,
+                CheckSubject::Output(index),
+                result.map_err(Error::from),
+            );
+        }
+        report.push(
+            Check::Balance,
+            CheckSubject::Tx,
+            spent_tx.verify_balance().map_err(Error::from),
+        );
+
+        report
+    }
+
+    /// Run every check of `Dbc::verify()`.
+    pub(crate) fn of_dbc(dbc: &Dbc, main_key: &MainKey) -> Self {
+        let mut report = Self::of_tx(&dbc.src_tx, &dbc.signed_spends);
+
+        let reason = dbc.reason();
+        for signed_spend in dbc.signed_spends.iter() {
+            report.push(
+                Check::ReasonConsistency,
+                CheckSubject::DbcId(*signed_spend.dbc_id()),
+                if signed_spend.reason() == reason {
+                    Ok(())
+                } else {
+                    Err(Error::SignedSpendReasonMismatch(*signed_spend.dbc_id()))
+                },
+            );
+        }
+
+        let subject = CheckSubject::DbcId(dbc.id());
+        report.push(
+            Check::CipherOutputMatch,
+            subject,
+            dbc.derived_key(main_key).and_then(|derived_key| {
+                let dbc_id = derived_key.dbc_id();
+                if dbc.src_tx.outputs.iter().any(|o| dbc_id.eq(o.dbc_id())) {
+                    Ok(())
+                } else {
+                    Err(Error::DbcCiphersNotPresentInTransactionOutput)
+                }
+            }),
+        );
+        report.push(Check::AmountMatch, subject, dbc.verify_amounts(main_key));
+
+        report
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.checks.iter() {
+            match &c.result {
+                Ok(()) => writeln!(f, "pass: {} of {}", c.check, c.subject)?,
+                Err(err) => writeln!(f, "FAIL: {} of {}: {err}", c.check, c.subject)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NetworkId, TransactionVerifier};
+
+    #[test]
+    fn report_holds_every_failed_check() -> Result<()> {
+        let mut rng = crate::rng::from_seed([0u8; 32]);
+        let (_, _, dbc, main_key) =
+            crate::dbc::tests::generate_dbc_and_its_main_key(100, &mut rng)?;
+
+        let report = dbc.verify_report(&main_key);
+        assert!(report.is_ok());
+        assert_eq!(report.result(), dbc.verify(&main_key));
+        assert!(TransactionVerifier::verify_report(&dbc.src_tx, &dbc.signed_spends).is_ok());
+
+        // Reorder the outputs, which breaks their range proofs, as those are bound to the
+        // position of each output, and invalidates the signatures over the tx. Claim that
+        // an input was created on another network. Then check the Dbc with a key it was
+        // not sent to.
+        let mut broken_dbc = dbc.clone();
+        broken_dbc.src_tx.outputs.swap(0, 1);
+        let mut signed_spend = broken_dbc.signed_spends.pop_first().unwrap();
+        let testnet_input = *signed_spend.dbc_id();
+        signed_spend.spend.dbc_creation_tx.network = NetworkId::Testnet;
+        let _ = broken_dbc.signed_spends.insert(signed_spend);
+        let other_key = MainKey::random_from_rng(&mut rng);
+
+        let report = broken_dbc.verify_report(&other_key);
+        let failed: BTreeSet<Check> = report.failures().map(|c| c.check).collect();
+        assert_eq!(
+            failed,
+            BTreeSet::from([
+                Check::InputNetwork,
+                Check::SpendSignature,
+                Check::InputSignature,
+                Check::RangeProof,
+                Check::CipherOutputMatch,
+                Check::AmountMatch
+            ])
+        );
+        assert!(report
+            .failures()
+            .any(|c| c.check == Check::RangeProof && c.subject == CheckSubject::Output(1)));
+        assert!(report
+            .failures()
+            .any(|c| c.check == Check::InputNetwork
+                && c.subject == CheckSubject::DbcId(testnet_input)));
+        // verify() fails on the first of them only.
+        assert_eq!(report.result(), broken_dbc.verify(&other_key));
+        assert!(report.to_string().contains("FAIL: range proof of output 1"));
+
+        Ok(())
+    }
+}